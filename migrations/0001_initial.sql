@@ -1,4 +1,6 @@
-CREATE TABLE statistics (
+-- The schema from the original `dbsetup.sql`. Existing databases already
+-- have these tables, so they're only created if missing.
+CREATE TABLE IF NOT EXISTS statistics (
     guild_id VARCHAR(20) NOT NULL,
     user_id VARCHAR(20) NOT NULL,
     date DATE NOT NULL,
@@ -7,9 +9,9 @@ CREATE TABLE statistics (
     PRIMARY KEY (guild_id, user_id, date)
 );
 
-CREATE TABLE reminders (
+CREATE TABLE IF NOT EXISTS reminders (
     id SERIAL PRIMARY KEY,
     user_id VARCHAR(20) NOT NULL,
     date TIMESTAMP NOT NULL,
     message VARCHAR(150)
-);
\ No newline at end of file
+);
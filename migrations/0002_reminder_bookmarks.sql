@@ -0,0 +1,18 @@
+-- `add_reminder` always stored a bookmark and guild, but the setup script
+-- never created the columns. Databases that were fixed by hand may have
+-- called the guild column `server_id`.
+ALTER TABLE reminders ADD COLUMN IF NOT EXISTS bookmark VARCHAR(32) NOT NULL DEFAULT '';
+ALTER TABLE reminders ADD COLUMN IF NOT EXISTS guild_id VARCHAR(20);
+UPDATE reminders SET bookmark = '' WHERE bookmark IS NULL;
+ALTER TABLE reminders ALTER COLUMN bookmark SET DEFAULT '', ALTER COLUMN bookmark SET NOT NULL;
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM information_schema.columns
+               WHERE table_schema = current_schema()
+                 AND table_name = 'reminders' AND column_name = 'server_id') THEN
+        UPDATE reminders SET guild_id = server_id WHERE guild_id IS NULL;
+        ALTER TABLE reminders DROP COLUMN server_id;
+    END IF;
+END
+$$;
@@ -0,0 +1,33 @@
+-- Recurring reminders, jump links, delivery in channels, retries and
+-- reminders for other people.
+ALTER TABLE reminders
+    ADD COLUMN IF NOT EXISTS recurrence VARCHAR(100),
+    ADD COLUMN IF NOT EXISTS channel_id VARCHAR(20),
+    ADD COLUMN IF NOT EXISTS message_id VARCHAR(20),
+    ADD COLUMN IF NOT EXISTS join_message_id VARCHAR(20),
+    ADD COLUMN IF NOT EXISTS in_channel BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS state VARCHAR(10) NOT NULL DEFAULT 'pending',
+    ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS retry_at TIMESTAMP;
+
+CREATE TABLE IF NOT EXISTS reminder_recipients (
+    reminder_id INTEGER NOT NULL REFERENCES reminders(id) ON DELETE CASCADE,
+    user_id VARCHAR(20),
+    role_id VARCHAR(20),
+    UNIQUE (reminder_id, user_id),
+    UNIQUE (reminder_id, role_id),
+    CHECK (user_id IS NOT NULL OR role_id IS NOT NULL)
+);
+
+CREATE TABLE IF NOT EXISTS failed_reminders (
+    id INTEGER PRIMARY KEY,
+    user_id VARCHAR(20) NOT NULL,
+    date TIMESTAMP NOT NULL,
+    message VARCHAR(150),
+    guild_id VARCHAR(20),
+    channel_id VARCHAR(20),
+    recurrence VARCHAR(100),
+    attempts INTEGER NOT NULL,
+    error TEXT NOT NULL,
+    failed_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+);
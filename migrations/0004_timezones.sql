@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS timezones (
+    user_id VARCHAR(20) PRIMARY KEY,
+    timezone VARCHAR(64) NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Statistics are counted per channel. Rows from before have no channel.
+ALTER TABLE statistics ADD COLUMN channel_id VARCHAR(20) NOT NULL DEFAULT '';
+ALTER TABLE statistics DROP CONSTRAINT statistics_pkey;
+ALTER TABLE statistics ADD PRIMARY KEY (guild_id, channel_id, user_id, date);
@@ -0,0 +1,8 @@
+-- Messages per guild and UTC hour, for seeing when a guild is active.
+CREATE TABLE hourly_statistics (
+    guild_id VARCHAR(20) NOT NULL,
+    hour TIMESTAMP NOT NULL,
+    messages INTEGER NOT NULL,
+    words INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, hour)
+);
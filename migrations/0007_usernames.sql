@@ -0,0 +1,7 @@
+-- The last known username of everyone counted in the statistics, for showing
+-- members who have left the guild.
+CREATE TABLE usernames (
+    user_id VARCHAR(20) PRIMARY KEY,
+    username VARCHAR(32) NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+);
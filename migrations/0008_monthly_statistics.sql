@@ -0,0 +1,11 @@
+-- Statistics older than the retention period, summed up per month. `month`
+-- is the first day of the month.
+CREATE TABLE monthly_statistics (
+    guild_id VARCHAR(20) NOT NULL,
+    channel_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    month DATE NOT NULL,
+    messages INTEGER NOT NULL,
+    words INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, channel_id, user_id, month)
+);
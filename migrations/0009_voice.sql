@@ -0,0 +1,21 @@
+-- Time spent in voice channels, not counting the AFK channel or being
+-- deafened. Sessions without an end are still going on.
+CREATE TABLE voice_sessions (
+    id SERIAL PRIMARY KEY,
+    guild_id VARCHAR(20) NOT NULL,
+    channel_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    ended_at TIMESTAMP
+);
+
+CREATE INDEX voice_sessions_open ON voice_sessions (guild_id, user_id) WHERE ended_at IS NULL;
+
+-- Seconds in voice per guild, user and UTC day, from sessions that ended.
+CREATE TABLE voice_statistics (
+    guild_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    date DATE NOT NULL,
+    seconds INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, user_id, date)
+);
@@ -0,0 +1,10 @@
+-- How often custom emoji are used per guild and UTC day, in messages and as
+-- reactions.
+CREATE TABLE emoji_statistics (
+    guild_id VARCHAR(20) NOT NULL,
+    emoji_id VARCHAR(20) NOT NULL,
+    date DATE NOT NULL,
+    messages INTEGER NOT NULL,
+    reactions INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, emoji_id, date)
+);
@@ -0,0 +1,10 @@
+-- Members joining and leaving guilds.
+CREATE TABLE member_events (
+    id SERIAL PRIMARY KEY,
+    guild_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    joined BOOLEAN NOT NULL,
+    at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX member_events_guild_at ON member_events (guild_id, at);
@@ -0,0 +1,21 @@
+-- Experience of guild members, earned by posting.
+CREATE TABLE member_xp (
+    guild_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    xp BIGINT NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
+
+-- Roles given to members when they reach a level.
+CREATE TABLE level_roles (
+    guild_id VARCHAR(20) NOT NULL,
+    level INTEGER NOT NULL,
+    role_id VARCHAR(20) NOT NULL,
+    PRIMARY KEY (guild_id, level)
+);
+
+-- The channel level-ups are announced in, for guilds that want them announced.
+CREATE TABLE level_announcements (
+    guild_id VARCHAR(20) PRIMARY KEY,
+    channel_id VARCHAR(20) NOT NULL
+);
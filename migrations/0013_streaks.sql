@@ -0,0 +1,56 @@
+-- The latest run of consecutive days a member posted on, and their longest.
+-- Kept apart from the daily statistics, which are rolled up after a while.
+CREATE TABLE member_streaks (
+    guild_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    started DATE NOT NULL,
+    last_active DATE NOT NULL,
+    longest INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
+
+-- Each run of consecutive days is an island of dates that are the same
+-- distance from their rank.
+INSERT INTO member_streaks (guild_id, user_id, started, last_active, longest)
+SELECT guild_id, user_id, MAX(started), MAX(ended), MAX(ended - started + 1)
+FROM (
+    SELECT guild_id, user_id, MIN(date) AS started, MAX(date) AS ended
+    FROM (
+        SELECT DISTINCT guild_id, user_id, date,
+               date - DENSE_RANK() OVER (PARTITION BY guild_id, user_id ORDER BY date)::INTEGER AS island
+        FROM statistics WHERE messages > 0
+    ) days
+    GROUP BY guild_id, user_id, island
+) islands
+GROUP BY guild_id, user_id;
+
+-- Milestones members reached, so each is only announced once.
+CREATE TABLE milestones (
+    guild_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    milestone VARCHAR(32) NOT NULL,
+    reached_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+    PRIMARY KEY (guild_id, user_id, milestone)
+);
+
+-- Milestones reached before they were announced aren't announced late.
+INSERT INTO milestones (guild_id, user_id, milestone)
+SELECT guild_id, user_id, 'messages-' || threshold
+FROM (
+    SELECT guild_id, user_id, SUM(messages) AS messages FROM (
+        SELECT guild_id, user_id, messages FROM statistics
+        UNION ALL
+        SELECT guild_id, user_id, messages FROM monthly_statistics
+    ) combined
+    GROUP BY guild_id, user_id
+) totals, (VALUES (1000), (10000)) AS thresholds(threshold)
+WHERE messages >= threshold;
+
+INSERT INTO milestones (guild_id, user_id, milestone)
+SELECT guild_id, user_id, 'streak-100' FROM member_streaks WHERE longest >= 100;
+
+-- The channel milestones are announced in, for guilds that want them announced.
+CREATE TABLE milestone_announcements (
+    guild_id VARCHAR(20) PRIMARY KEY,
+    channel_id VARCHAR(20) NOT NULL
+);
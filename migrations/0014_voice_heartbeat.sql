@@ -0,0 +1,14 @@
+-- When the bot last saw each voice session going on, so sessions left open
+-- by a crash end then instead of counting the time the bot was away.
+ALTER TABLE voice_sessions ADD COLUMN last_seen_at TIMESTAMP;
+UPDATE voice_sessions SET last_seen_at = COALESCE(ended_at, started_at);
+ALTER TABLE voice_sessions ALTER COLUMN last_seen_at SET NOT NULL;
+
+-- A user can only have one session going on per guild. Extra sessions opened
+-- by events handled at the same time end without counting.
+UPDATE voice_sessions SET ended_at = started_at
+WHERE ended_at IS NULL AND id NOT IN (
+    SELECT MIN(id) FROM voice_sessions WHERE ended_at IS NULL GROUP BY guild_id, user_id
+);
+DROP INDEX voice_sessions_open;
+CREATE UNIQUE INDEX voice_sessions_open ON voice_sessions (guild_id, user_id) WHERE ended_at IS NULL;
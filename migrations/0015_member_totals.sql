@@ -0,0 +1,17 @@
+-- Messages and words of each member since they were first counted, kept as
+-- running totals so milestones can be checked without summing up history.
+CREATE TABLE member_totals (
+    guild_id VARCHAR(20) NOT NULL,
+    user_id VARCHAR(20) NOT NULL,
+    messages BIGINT NOT NULL,
+    words BIGINT NOT NULL,
+    PRIMARY KEY (guild_id, user_id)
+);
+
+INSERT INTO member_totals (guild_id, user_id, messages, words)
+SELECT guild_id, user_id, SUM(messages), SUM(words) FROM (
+    SELECT guild_id, user_id, messages, words FROM statistics
+    UNION ALL
+    SELECT guild_id, user_id, messages, words FROM monthly_statistics
+) combined
+GROUP BY guild_id, user_id;
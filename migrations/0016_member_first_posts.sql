@@ -0,0 +1,12 @@
+-- The first day members posted on after joining, recorded when they post,
+-- since the daily statistics it could be found in are rolled up after a while.
+ALTER TABLE member_events ADD COLUMN first_posted DATE;
+
+UPDATE member_events e SET first_posted = (
+    SELECT MIN(date) FROM statistics s
+    WHERE s.guild_id = e.guild_id AND s.user_id = e.user_id AND s.date >= CAST(e.at AS DATE)
+)
+WHERE joined;
+
+CREATE INDEX member_events_not_posted ON member_events (guild_id, user_id)
+WHERE joined AND first_posted IS NULL;
@@ -0,0 +1,6 @@
+-- Recurring reminders skip occurrences that can't be delivered instead of
+-- being given up on, so the same reminder can fail on several dates.
+ALTER TABLE failed_reminders DROP CONSTRAINT failed_reminders_pkey;
+ALTER TABLE failed_reminders ADD PRIMARY KEY (id, date);
+
+CREATE INDEX failed_reminders_user ON failed_reminders (user_id, failed_at);
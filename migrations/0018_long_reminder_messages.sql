@@ -0,0 +1,4 @@
+-- Reminder messages are limited by the bot rather than the column, so that
+-- long ones can be stored and moved to failed_reminders alike.
+ALTER TABLE reminders ALTER COLUMN message TYPE TEXT;
+ALTER TABLE failed_reminders ALTER COLUMN message TYPE TEXT;
@@ -0,0 +1,154 @@
+use plotters::prelude::*;
+use png::{BitDepth, ColorType, Encoder};
+use std::cmp;
+use std::fmt::Display;
+use std::sync::Once;
+
+use command_error::CommandError;
+use connectionpool::DailyStatistics;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 400;
+const HEATMAP_HEIGHT: u32 = 300;
+
+static WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// How many dates are written below the bars at most.
+const MAX_X_LABELS: usize = 10;
+
+/// The font of all text in charts, embedded so charts don't depend on the
+/// fonts installed where the bot runs.
+static FONT: &[u8] = include_bytes!("../assets/DejaVuSans.ttf");
+
+static REGISTER_FONT: Once = Once::new();
+
+/// Makes the embedded font the one used for `sans-serif`, the first time a
+/// chart is drawn.
+fn register_font() {
+    REGISTER_FONT.call_once(|| {
+        if plotters::style::register_font("sans-serif", FontStyle::Normal, FONT).is_err() {
+            error!("Failed to load the font of charts");
+        }
+    });
+}
+
+/// Draws the messages per day as a bar chart, returned as a PNG.
+pub fn activity_chart(title: &str, daily: &[DailyStatistics]) -> Result<Vec<u8>, CommandError> {
+    register_font();
+    let mut buffer = vec![0; (WIDTH * HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(chart_error)?;
+
+        let days = cmp::max(daily.len(), 1) as u32;
+        let max = daily.iter().map(|d| d.messages).max().unwrap_or(0);
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 22))
+            .margin(15)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d((0..days).into_segmented(), 0..cmp::max(max + max / 10, 1))
+            .map_err(chart_error)?;
+
+        let label = |x: &SegmentValue<u32>| match *x {
+            SegmentValue::CenterOf(i) => daily
+                .get(i as usize)
+                .map(|d| d.date.format("%m-%d").to_string())
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .x_labels(cmp::min(days as usize, MAX_X_LABELS))
+            .x_label_formatter(&label)
+            .y_desc("Messages")
+            .draw()
+            .map_err(chart_error)?;
+
+        chart
+            .draw_series(
+                Histogram::vertical(&chart)
+                    .style(RGBColor(88, 101, 242).filled())
+                    .margin(1)
+                    .data(daily.iter().enumerate().map(|(i, d)| (i as u32, d.messages))),
+            )
+            .map_err(chart_error)?;
+
+        root.present().map_err(chart_error)?;
+    }
+
+    encode_png(&buffer, WIDTH, HEIGHT)
+}
+
+/// Draws messages per weekday, starting on monday, and hour as a grid of
+/// squares that are darker the more active the hour is, returned as a PNG.
+pub fn heatmap(title: &str, grid: &[[i64; 24]; 7]) -> Result<Vec<u8>, CommandError> {
+    register_font();
+    let mut buffer = vec![0; (WIDTH * HEATMAP_HEIGHT * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (WIDTH, HEATMAP_HEIGHT)).into_drawing_area();
+        root.fill(&WHITE).map_err(chart_error)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 22))
+            .margin(15)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0..24, 0..7)
+            .map_err(chart_error)?;
+
+        // Monday goes at the top.
+        let weekday = |y: &i32| match 6 - *y {
+            day @ 0..=6 => WEEKDAYS[day as usize].to_owned(),
+            _ => String::new(),
+        };
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_labels(24)
+            .y_labels(7)
+            .x_desc("Hour")
+            .y_label_formatter(&weekday)
+            .y_label_offset(-(HEATMAP_HEIGHT as i32) / 20)
+            .x_label_offset(WIDTH as i32 / 70)
+            .draw()
+            .map_err(chart_error)?;
+
+        let max = grid.iter().flat_map(|day| day.iter()).cloned().max().unwrap_or(0);
+        let squares = grid.iter().enumerate().flat_map(|(day, hours)| {
+            hours.iter().enumerate().map(move |(hour, &messages)| {
+                let y = 6 - day as i32;
+                let shade = if max > 0 { messages as f64 / max as f64 } else { 0.0 };
+                let color = RGBColor(
+                    (235.0 - 147.0 * shade) as u8,
+                    (238.0 - 137.0 * shade) as u8,
+                    (255.0 - 13.0 * shade) as u8,
+                );
+                Rectangle::new([(hour as i32, y), (hour as i32 + 1, y + 1)], color.filled())
+            })
+        });
+        chart.draw_series(squares).map_err(chart_error)?;
+
+        root.present().map_err(chart_error)?;
+    }
+
+    encode_png(&buffer, WIDTH, HEATMAP_HEIGHT)
+}
+
+/// Encodes an RGB buffer as a PNG.
+fn encode_png(buffer: &[u8], width: u32, height: u32) -> Result<Vec<u8>, CommandError> {
+    let mut png = vec![];
+    {
+        let mut encoder = Encoder::new(&mut png, width, height);
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(chart_error)?;
+        writer.write_image_data(buffer).map_err(chart_error)?;
+    }
+    Ok(png)
+}
+
+fn chart_error<E: Display>(why: E) -> CommandError {
+    CommandError::Generic(format!("Failed to draw chart: {}", why))
+}
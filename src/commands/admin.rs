@@ -1,6 +1,10 @@
-use serenity::model::id::MessageId;
+use chrono::offset::TimeZone;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::model::misc::Mentionable;
+use serenity::utils;
 
 use command_error::CommandError;
+use util::{self, print_or_log_error};
 
 command!(purge(_ctx, msg, args) {
     // Get the number of messages to delete.
@@ -19,3 +23,60 @@ command!(purge(_ctx, msg, args) {
 
     channel.delete_messages(messages.into_iter())?;
 });
+
+/// Lists the most recent reminders from this guild that could not be delivered.
+command!(failedreminders(ctx, msg, args) {
+    let limit = args.single::<i64>().unwrap_or(5).max(1).min(10);
+    let guild_id = msg.guild_id()
+        .ok_or(CommandError::Generic("Could not get guild.".to_owned()))?;
+
+    let failed = util::get_pool(ctx).get_failed_reminders(guild_id, limit)?;
+    if failed.is_empty() {
+        print_or_log_error("No failed reminders.", &msg.channel_id);
+        return Ok(());
+    }
+
+    let result = failed
+        .into_iter()
+        .map(|f| {
+            format!(
+                "#{} for user {}, due {}{}, failed {} after {} attempts: {}",
+                f.id,
+                f.user_id,
+                f.timezone.from_utc_datetime(&f.date).format("%Y-%m-%d %H:%M %Z"),
+                f.recurrence.as_ref().map_or(String::new(), |r| format!(", repeating {}", r.describe())),
+                f.timezone.from_utc_datetime(&f.failed_at).format("%Y-%m-%d %H:%M %Z"),
+                f.attempts,
+                f.error
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    print_or_log_error(&format!("```\n{}\n```", result), &msg.channel_id);
+});
+
+/// Sets the channel milestones of members are announced in, or stops
+/// announcing them. Shows the channel when given nothing else.
+command!(milestones(ctx, msg, args) {
+    let guild_id = msg.guild_id()
+        .ok_or(CommandError::Generic("Could not get guild.".to_owned()))?;
+    let mut pool = util::get_pool(ctx);
+
+    let text = args.full().trim();
+    let response = if text.is_empty() {
+        match pool.get_milestone_announcement_channel(guild_id)? {
+            Some(id) => format!("Milestones are announced in {}.", id.mention()),
+            None => "Milestones aren't announced.".to_owned(),
+        }
+    } else if text == "off" {
+        pool.set_milestone_announcement_channel(guild_id, None)?;
+        "Milestones won't be announced anymore.".to_owned()
+    } else if let Some(id) = utils::parse_channel(text) {
+        pool.set_milestone_announcement_channel(guild_id, Some(ChannelId(id)))?;
+        format!("Milestones will be announced in {}.", ChannelId(id).mention())
+    } else {
+        "Usage: `?milestones #channel` or `?milestones off`".to_owned()
+    };
+    print_or_log_error(&response, &msg.channel_id);
+});
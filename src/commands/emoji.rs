@@ -0,0 +1,203 @@
+use chrono::offset::Utc;
+use serenity::client::Context;
+use serenity::model::channel::{Channel, Reaction, ReactionType};
+use serenity::model::id::EmojiId;
+use std::cmp;
+use std::collections::HashMap;
+
+use super::statistics::MAX_DAYS;
+use connectionpool::{EmojiDelta, EmojiKey, EmojiStatistics};
+use util;
+
+/// How many of the most used emoji are listed.
+const MOST_USED: usize = 10;
+
+/// How many of the least used emoji that were used at all are listed.
+const LEAST_USED: usize = 5;
+
+/// Counts a custom emoji added as a reaction in a guild.
+pub fn record_reaction(ctx: &Context, reaction: &Reaction) {
+    let emoji_id = match reaction.emoji {
+        ReactionType::Custom { id, .. } => id,
+        _ => return,
+    };
+    if reaction.user_id.find().map_or(false, |u| u.read().bot) {
+        return;
+    }
+    let guild_id = match reaction.channel_id.find() {
+        Some(Channel::Guild(channel)) => channel.read().guild_id,
+        _ => return,
+    };
+
+    let key = EmojiKey {
+        guild_id,
+        emoji_id,
+        date: Utc::now().naive_utc().date(),
+    };
+    util::get_stats_aggregator(ctx).record_emoji(key, EmojiDelta { messages: 0, reactions: 1 });
+}
+
+/// The custom emoji in a message, each once.
+pub fn custom_emoji(content: &str) -> Vec<EmojiId> {
+    let mut emoji = vec![];
+    for part in content.split('<').skip(1) {
+        // Emoji are written as `<:name:id>`, or `<a:name:id>` if animated.
+        let inner = match part.find('>') {
+            Some(end) => &part[..end],
+            None => continue,
+        };
+        let mut fields = inner.split(':');
+        match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(""), Some(name), Some(id), None) | (Some("a"), Some(name), Some(id), None)
+                if !name.is_empty() =>
+            {
+                if let Ok(id) = id.parse() {
+                    if !emoji.contains(&EmojiId(id)) {
+                        emoji.push(EmojiId(id));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    emoji
+}
+
+/// List the most and least used emoji of the guild, and the ones nobody used.
+command!(emojistats(ctx, msg, args) {
+    let days = cmp::min(cmp::max(args.single::<u32>().unwrap_or(util::DEFAULT_DAYS), 1), MAX_DAYS);
+    let mut pool = util::get_pool(ctx);
+
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let since = util::first_day(&mut pool, msg.author.id, days)?;
+    let statistics = pool.get_emoji_statistics(guild_id, since)?;
+
+    let emoji = match guild_id.find() {
+        Some(guild) => guild
+            .read()
+            .emojis
+            .values()
+            .map(|e| EmojiUsage {
+                id: e.id,
+                mention: format!("<{}:{}:{}>", if e.animated { "a" } else { "" }, e.name, e.id),
+                name: e.name.clone(),
+                messages: 0,
+                reactions: 0,
+            })
+            .collect(),
+        None => vec![],
+    };
+    if emoji.is_empty() {
+        util::print_or_log_error("This server has no emoji of its own.", &msg.channel_id);
+        return Ok(());
+    }
+
+    let mut lines = vec![format!(
+        "Emoji use the last {} days (since {}):",
+        days, since.format("%Y-%m-%d")
+    )];
+    lines.extend(describe_emoji_usage(count_uses(emoji, &statistics)));
+    util::say_in_parts(&msg.channel_id, &lines);
+});
+
+/// How often one of the emoji of a guild was used.
+#[derive(Debug, Clone, PartialEq)]
+struct EmojiUsage {
+    id: EmojiId,
+    mention: String,
+    name: String,
+    messages: i64,
+    reactions: i64,
+}
+
+impl EmojiUsage {
+    fn uses(&self) -> i64 {
+        self.messages + self.reactions
+    }
+}
+
+/// Adds the uses in `statistics` to the emoji of a guild, and sorts them from
+/// most to least used.
+fn count_uses(mut emoji: Vec<EmojiUsage>, statistics: &[EmojiStatistics]) -> Vec<EmojiUsage> {
+    let by_id: HashMap<EmojiId, &EmojiStatistics> = statistics.iter().map(|s| (s.emoji_id, s)).collect();
+    for e in &mut emoji {
+        if let Some(s) = by_id.get(&e.id) {
+            e.messages = s.messages;
+            e.reactions = s.reactions;
+        }
+    }
+    emoji.sort_by(|a, b| b.uses().cmp(&a.uses()).then_with(|| a.name.cmp(&b.name)));
+    emoji
+}
+
+/// One line per emoji in the most and least used, sorted from most to least
+/// used, then the unused ones together.
+fn describe_emoji_usage(emoji: Vec<EmojiUsage>) -> Vec<String> {
+    let (used, unused): (Vec<_>, Vec<_>) = emoji.into_iter().partition(|e| e.uses() > 0);
+    let describe = |e: &EmojiUsage| {
+        format!(
+            "{} `:{}:` {} ({} in messages, {} as reactions)",
+            e.mention, e.name, e.uses(), e.messages, e.reactions
+        )
+    };
+
+    let mut lines = vec![];
+    if used.is_empty() {
+        lines.push("None of the emoji were used.".to_owned());
+    } else {
+        lines.push("**Most used**".to_owned());
+        lines.extend(used.iter().take(MOST_USED).map(&describe));
+        let least = cmp::min(LEAST_USED, used.len().saturating_sub(MOST_USED));
+        if least > 0 {
+            lines.push("**Least used**".to_owned());
+            lines.extend(used[used.len() - least..].iter().map(&describe));
+        }
+    }
+    if !unused.is_empty() {
+        lines.push(format!("**Unused** ({})", unused.len()));
+        lines.push(unused.iter().map(|e| e.mention.as_str()).collect::<Vec<_>>().join(" "));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emoji(id: u64, name: &str) -> EmojiUsage {
+        EmojiUsage {
+            id: EmojiId(id),
+            mention: format!("<:{}:{}>", name, id),
+            name: name.to_owned(),
+            messages: 0,
+            reactions: 0,
+        }
+    }
+
+    #[test]
+    fn emoji_in_messages() {
+        let content = "<:pog:123> hi <a:dance:456> <:pog:123> <@789> <:bad:x> <:nope:1";
+        assert_eq!(custom_emoji(content), vec![EmojiId(123), EmojiId(456)]);
+    }
+
+    #[test]
+    fn emoji_are_sorted_by_uses() {
+        let statistics = vec![
+            EmojiStatistics { emoji_id: EmojiId(2), messages: 1, reactions: 4 },
+            EmojiStatistics { emoji_id: EmojiId(3), messages: 2, reactions: 0 },
+            EmojiStatistics { emoji_id: EmojiId(9), messages: 8, reactions: 0 },
+        ];
+        let counted = count_uses(vec![emoji(1, "a"), emoji(2, "b"), emoji(3, "c")], &statistics);
+        let names: Vec<&str> = counted.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c", "a"]);
+
+        let lines = describe_emoji_usage(counted);
+        assert_eq!(lines[0], "**Most used**");
+        assert_eq!(lines[1], "<:b:2> `:b:` 5 (1 in messages, 4 as reactions)");
+        assert_eq!(lines[3], "**Unused** (1)");
+        assert_eq!(lines[4], "<:a:1>");
+    }
+}
@@ -0,0 +1,207 @@
+use chrono::NaiveDate;
+use serenity::CACHE;
+use serenity::client::Context;
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use typemap::Key;
+
+use command_error::CommandError;
+use connectionpool::{ConnectionPool, Statistics, StatisticsOrder};
+use util;
+
+static PREVIOUS_PAGE: &str = "\u{2b05}\u{fe0f}";
+static NEXT_PAGE: &str = "\u{27a1}\u{fe0f}";
+
+/// The most users shown on one page, to stay below the message length limit.
+/// A line takes up to about 105 characters with a 32 character name marked
+/// as having left, so 15 lines and the header fit within 2000.
+pub const MAX_PAGE_SIZE: i64 = 15;
+
+/// How long a leaderboard can be paged through after it was posted.
+const PAGING_SECS: u64 = 60 * 60;
+
+/// What a leaderboard posted by `?stats` shows.
+#[derive(Debug, Clone)]
+pub struct Leaderboard {
+    pub guild_id: GuildId,
+    /// Who asked for it, and is the only one who can turn its pages.
+    pub author: UserId,
+    pub days: u32,
+    pub since: NaiveDate,
+    pub channel_id: Option<ChannelId>,
+    pub order: StatisticsOrder,
+    pub page_size: i64,
+    /// The page shown, starting at 1.
+    pub page: i64,
+}
+
+/// A leaderboard that was posted, kept so it can be paged through.
+pub struct PostedLeaderboard {
+    leaderboard: Leaderboard,
+    pages: i64,
+    posted: Instant,
+}
+
+/// Leaderboards that can still be paged through, by the message showing them.
+pub struct Leaderboards;
+
+impl Key for Leaderboards {
+    type Value = HashMap<MessageId, PostedLeaderboard>;
+}
+
+/// Posts a leaderboard in the channel of `msg`, with reactions for turning
+/// its pages if it has more than one.
+pub fn post(ctx: &Context, msg: &Message, leaderboard: Leaderboard) -> Result<(), CommandError> {
+    let (text, pages) = render(&mut util::get_pool(ctx), &leaderboard)?;
+    let message = msg.channel_id.say(&text)?;
+    if pages <= 1 {
+        return Ok(());
+    }
+
+    message.react(ReactionType::Unicode(PREVIOUS_PAGE.to_owned()))?;
+    message.react(ReactionType::Unicode(NEXT_PAGE.to_owned()))?;
+
+    let mut data = ctx.data.lock();
+    let leaderboards = data.get_mut::<Leaderboards>().unwrap();
+    leaderboards.retain(|_, l| l.posted.elapsed() < Duration::from_secs(PAGING_SECS));
+    leaderboards.insert(message.id, PostedLeaderboard {
+        leaderboard,
+        pages,
+        posted: Instant::now(),
+    });
+
+    Ok(())
+}
+
+/// Turns the page of a leaderboard when its author reacts with an arrow.
+/// Both adding and removing a reaction count, so no permission is needed to
+/// remove reactions.
+pub fn turn_page(ctx: &Context, reaction: &Reaction) {
+    // Variation selectors may or may not be sent along with the arrows.
+    let step = match reaction.emoji {
+        ReactionType::Unicode(ref emoji) => match emoji.trim_end_matches('\u{fe0f}') {
+            e if e == PREVIOUS_PAGE.trim_end_matches('\u{fe0f}') => -1,
+            e if e == NEXT_PAGE.trim_end_matches('\u{fe0f}') => 1,
+            _ => return,
+        },
+        _ => return,
+    };
+    if reaction.user_id == CACHE.read().user.id {
+        return;
+    }
+
+    let (mut leaderboard, pages) = {
+        let data = ctx.data.lock();
+        match data.get::<Leaderboards>().unwrap().get(&reaction.message_id) {
+            Some(p) if p.leaderboard.author == reaction.user_id => (p.leaderboard.clone(), p.pages),
+            _ => return,
+        }
+    };
+    let page = leaderboard.page + step;
+    if page < 1 || page > pages {
+        return;
+    }
+    leaderboard.page = page;
+
+    let result = render(&mut util::get_pool(ctx), &leaderboard).and_then(|(text, pages)| {
+        reaction
+            .channel_id
+            .edit_message(reaction.message_id, |m| m.content(&text))
+            .map(|_| pages)
+            .map_err(CommandError::from)
+    });
+    let pages = match result {
+        Ok(pages) => pages,
+        Err(why) => {
+            error!("Failed to turn leaderboard page: {}", why);
+            return;
+        }
+    };
+
+    let mut data = ctx.data.lock();
+    if let Some(posted) = data.get_mut::<Leaderboards>().unwrap().get_mut(&reaction.message_id) {
+        posted.leaderboard = leaderboard;
+        posted.pages = pages;
+    }
+}
+
+/// Writes the page of a leaderboard, and counts how many pages it has.
+fn render(pool: &mut ConnectionPool, leaderboard: &Leaderboard) -> Result<(String, i64), CommandError> {
+    let (statistics, total) = pool.get_statistics(
+        leaderboard.guild_id,
+        leaderboard.since,
+        leaderboard.channel_id,
+        leaderboard.order,
+        leaderboard.page_size,
+        (leaderboard.page - 1) * leaderboard.page_size,
+    )?;
+    let pages = (total + leaderboard.page_size - 1) / leaderboard.page_size;
+
+    let place = leaderboard
+        .channel_id
+        .map(|c| format!(" in #{}", util::channel_name(c)))
+        .unwrap_or_default();
+    let header = format!(
+        "Most active users by {}{} the last {} days (since {})",
+        leaderboard.order.name(),
+        place,
+        leaderboard.days,
+        leaderboard.since.format("%Y-%m-%d")
+    );
+    if statistics.is_empty() {
+        return Ok((format!("{}:\nNobody to show on page {}.", header, leaderboard.page), pages));
+    }
+
+    let rank = (leaderboard.page - 1) * leaderboard.page_size + 1;
+    let text = format!(
+        "{}, page {} of {}:\n```\n{}\n```",
+        header,
+        leaderboard.page,
+        pages,
+        describe(leaderboard.guild_id, &statistics, rank)
+    );
+    Ok((text, pages))
+}
+
+/// One line per user, numbered from `rank`.
+fn describe(guild_id: GuildId, statistics: &[Statistics], rank: i64) -> String {
+    let names: Vec<String> = statistics
+        .iter()
+        .map(|s| util::member_name(guild_id, s.user_id, s.username.as_ref()))
+        .collect();
+    let rank_width = util::digits(rank + statistics.len() as i64 - 1);
+    let name_width = names.iter().map(|n| n.chars().count()).max().unwrap_or(5);
+    let words_width = statistics
+        .iter()
+        .map(|ref s| util::digits(s.words))
+        .max()
+        .unwrap_or(5);
+    let messages_width = statistics
+        .iter()
+        .map(|ref s| util::digits(s.messages))
+        .max()
+        .unwrap_or(5);
+
+    statistics
+        .iter()
+        .zip(names.iter())
+        .enumerate()
+        .map(|(i, (s, name))| {
+            format!(
+                "{:>rw$}. {:<nw$} | {:>mw$} messages | {:>ww$} words | {:.1} words per message",
+                rank + i as i64,
+                name,
+                s.messages,
+                s.words,
+                s.words as f64 / s.messages.max(1) as f64,
+                rw = rank_width,
+                nw = name_width,
+                mw = messages_width,
+                ww = words_width
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,78 @@
+use chrono::offset::Utc;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::id::{GuildId, UserId};
+use std::cmp;
+
+use super::statistics::MAX_DAYS;
+use command_error::CommandError;
+use connectionpool::MemberStatistics;
+use util;
+
+/// Records a member joining or leaving a guild.
+pub fn record_member_event(ctx: &Context, guild_id: GuildId, user_id: UserId, joined: bool) {
+    let now = Utc::now().naive_utc();
+    if let Err(why) = util::get_pool(ctx).add_member_event(guild_id, user_id, joined, now) {
+        error!("Failed to record member event: {}", why);
+    }
+}
+
+/// Shows how many members joined and left the last `x` days, and how many of
+/// the new members posted within their first week.
+pub fn stats(ctx: &Context, msg: &Message, text: &str) -> Result<(), CommandError> {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let mut pool = util::get_pool(ctx);
+
+    let days = text
+        .split_whitespace()
+        .filter_map(|d| d.parse().ok())
+        .next()
+        .unwrap_or(util::DEFAULT_DAYS);
+    let days = cmp::min(cmp::max(days, 1), MAX_DAYS);
+    let since = util::first_day(&mut pool, msg.author.id, days)?;
+    let statistics = pool.get_member_statistics(guild_id, since)?;
+
+    util::print_or_log_error(&format!(
+        "Members the last {} days (since {}):\n```\n{}\n```",
+        days, since.format("%Y-%m-%d"),
+        describe_member_statistics(&statistics)
+    ), &msg.channel_id);
+    Ok(())
+}
+
+fn describe_member_statistics(statistics: &MemberStatistics) -> String {
+    let mut lines = vec![
+        format!("Joined: {}", statistics.joins),
+        format!("Left: {}", statistics.leaves),
+        format!("Net growth: {:+}", statistics.joins - statistics.leaves),
+    ];
+    if statistics.new_members > 0 {
+        lines.push(format!(
+            "Posted in their first week: {} of {} new members ({}%)",
+            statistics.posted_first_week,
+            statistics.new_members,
+            statistics.posted_first_week * 100 / statistics.new_members
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn member_statistics_summary() {
+        let statistics = MemberStatistics { joins: 4, leaves: 6, new_members: 3, posted_first_week: 1 };
+        assert_eq!(
+            describe_member_statistics(&statistics),
+            "Joined: 4\nLeft: 6\nNet growth: -2\nPosted in their first week: 1 of 3 new members (33%)"
+        );
+
+        let statistics = MemberStatistics { joins: 0, leaves: 0, new_members: 0, posted_first_week: 0 };
+        assert_eq!(describe_member_statistics(&statistics), "Joined: 0\nLeft: 0\nNet growth: +0");
+    }
+}
@@ -1,4 +1,10 @@
 pub mod admin;
+pub mod emoji;
+pub mod leaderboard;
+pub mod members;
+pub mod rank;
 pub mod remindme;
 pub mod roles;
 pub mod statistics;
+pub mod timezone;
+pub mod voice;
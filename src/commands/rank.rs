@@ -0,0 +1,193 @@
+use serenity::model::id::{ChannelId, RoleId, UserId};
+use serenity::model::misc::Mentionable;
+use serenity::utils;
+use std::cmp;
+
+use levels::LevelConfig;
+use util;
+
+/// How many members `?levels` lists a page.
+const LEVELS_PAGE_SIZE: i64 = 10;
+
+/// The last page `?levels` shows, far beyond the members of any guild.
+const MAX_LEVELS_PAGE: i64 = 100_000;
+
+/// How many characters wide the progress bar of `?rank` is.
+const PROGRESS_BAR_WIDTH: i64 = 20;
+
+/// Show the level, XP and rank of the author, or of someone they mention.
+command!(rank(ctx, msg, args) {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let user_id = args
+        .full()
+        .split_whitespace()
+        .filter_map(utils::parse_username)
+        .next()
+        .map_or(msg.author.id, UserId);
+
+    let mut pool = util::get_pool(ctx);
+    let username = pool.get_username(user_id)?;
+    let name = util::member_name(guild_id, user_id, username.as_ref());
+    let text = match pool.get_xp_rank(guild_id, user_id)? {
+        Some((xp, rank, ranked)) => describe_rank(&util::get_level_config(ctx), &name, xp, rank, ranked),
+        None => format!("{} hasn't earned any XP yet.", name),
+    };
+    util::print_or_log_error(&text, &msg.channel_id);
+});
+
+/// List the members with the most XP and their levels, optionally on another
+/// page than the first.
+command!(levels(ctx, msg, args) {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let page = cmp::min(cmp::max(args.single::<i64>().unwrap_or(1), 1), MAX_LEVELS_PAGE);
+    let config = util::get_level_config(ctx);
+
+    let statistics = util::get_pool(ctx).get_xp_leaderboard(
+        guild_id,
+        LEVELS_PAGE_SIZE,
+        (page - 1) * LEVELS_PAGE_SIZE,
+    )?;
+    if statistics.is_empty() {
+        util::print_or_log_error("Nobody has earned any XP on that page.", &msg.channel_id);
+        return Ok(());
+    }
+
+    let names: Vec<String> = statistics
+        .iter()
+        .map(|s| util::member_name(guild_id, s.user_id, s.username.as_ref()))
+        .collect();
+    let name_width = names.iter().map(|n| n.chars().count()).max().unwrap_or(5);
+    let first = (page - 1) * LEVELS_PAGE_SIZE + 1;
+    let rank_width = util::digits(first + statistics.len() as i64 - 1);
+    let xp_width = util::digits(statistics[0].xp);
+
+    let result = statistics
+        .iter()
+        .zip(names.iter())
+        .enumerate()
+        .map(|(i, (s, name))| {
+            format!(
+                "{:>rw$}. {:<nw$} | level {:>3} | {:>xw$} XP",
+                first + i as i64, name, config.level_for_xp(s.xp), s.xp,
+                rw = rank_width, nw = name_width, xw = xp_width
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    util::print_or_log_error(&format!("Levels, page {}:\n```\n{}\n```", page, result), &msg.channel_id);
+});
+
+/// Manage the roles given for reaching levels, and where level-ups are
+/// announced. Lists them when given nothing else.
+command!(levelroles(ctx, msg, args) {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let mut pool = util::get_pool(ctx);
+    let words: Vec<&str> = args.full().split_whitespace().collect();
+
+    let response = match words.as_slice() {
+        ["add", level, role] => match (level.parse::<i32>(), utils::parse_role(role)) {
+            (Ok(level), Some(role_id)) if level > 0 => {
+                pool.set_level_role(guild_id, level, RoleId(role_id))?;
+                format!("Members will get {} when they reach level {}.", role_name(RoleId(role_id)), level)
+            }
+            _ => "Usage: `?levelroles add level @role`".to_owned(),
+        },
+        ["remove", level] => match level.parse::<i32>() {
+            Ok(level) => if pool.remove_level_role(guild_id, level)? {
+                format!("Level {} doesn't give a role anymore.", level)
+            } else {
+                format!("Level {} doesn't give a role.", level)
+            },
+            Err(_) => "Usage: `?levelroles remove level`".to_owned(),
+        },
+        ["announce", "off"] => {
+            pool.set_level_announcement_channel(guild_id, None)?;
+            "Level-ups won't be announced anymore.".to_owned()
+        }
+        ["announce", channel] => match utils::parse_channel(channel) {
+            Some(id) => {
+                pool.set_level_announcement_channel(guild_id, Some(ChannelId(id)))?;
+                format!("Level-ups will be announced in {}.", ChannelId(id).mention())
+            }
+            None => "Usage: `?levelroles announce #channel` or `?levelroles announce off`".to_owned(),
+        },
+        [] => {
+            let mut lines: Vec<String> = pool
+                .get_level_roles(guild_id)?
+                .iter()
+                .map(|r| format!("Level {}: {}", r.level, role_name(r.role_id)))
+                .collect();
+            if lines.is_empty() {
+                lines.push("No levels give a role.".to_owned());
+            }
+            lines.push(match pool.get_level_announcement_channel(guild_id)? {
+                Some(id) => format!("Level-ups are announced in {}.", id.mention()),
+                None => "Level-ups aren't announced.".to_owned(),
+            });
+            lines.join("\n")
+        }
+        _ => "Usage: `?levelroles add level @role`, `?levelroles remove level` or \
+              `?levelroles announce #channel|off`".to_owned(),
+    };
+    util::print_or_log_error(&response, &msg.channel_id);
+});
+
+/// Gets the name of a role from the cache, falling back to its id.
+fn role_name(role_id: RoleId) -> String {
+    match role_id.find() {
+        Some(role) => format!("**{}**", role.name),
+        None => format!("{}", role_id),
+    }
+}
+
+/// The level, XP and rank of a member, and how far they are from the next
+/// level.
+fn describe_rank(config: &LevelConfig, name: &str, xp: i64, rank: i64, ranked: i64) -> String {
+    let level = config.level_for_xp(xp);
+    let start = config.xp_for_level(level);
+    let next = config.xp_for_level(level + 1);
+    let progress = (xp - start) * PROGRESS_BAR_WIDTH / (next - start);
+    format!(
+        "**{}** is level {} with {} XP, rank #{} of {}.\n\
+         `[{}{}]` {} XP to level {}",
+        name, level, xp, rank, ranked,
+        "#".repeat(progress as usize),
+        "-".repeat((PROGRESS_BAR_WIDTH - progress) as usize),
+        next - xp, level + 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn rank_shows_progress_to_the_next_level() {
+        let config = LevelConfig {
+            xp_per_message: 20,
+            cooldown: Duration::seconds(60),
+            base: 100.0,
+            exponent: 1.5,
+        };
+        assert_eq!(
+            describe_rank(&config, "Horace", 183, 2, 7),
+            "**Horace** is level 1 with 183 XP, rank #2 of 7.\n\
+             `[#########-----------]` 100 XP to level 2"
+        );
+        assert_eq!(
+            describe_rank(&config, "Horace", 0, 1, 1),
+            "**Horace** is level 0 with 0 XP, rank #1 of 1.\n\
+             `[--------------------]` 100 XP to level 1"
+        );
+    }
+}
@@ -1,124 +1,681 @@
 use std::str::FromStr;
-use std::{thread, time};
+use std::time;
 
-use chrono::Duration;
-use chrono::offset::Utc;
+use chrono::offset::{TimeZone, Utc};
+use chrono::{DateTime, Duration, NaiveDateTime};
+use chrono_tz::Tz;
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
-use serenity::model::id::{UserId, GuildId};
+use serenity::CACHE;
+use serenity::client::Context;
+use serenity::http::{HttpError, StatusCode};
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::model::id::{ChannelId, RoleId, UserId, GuildId};
+use serenity::model::misc::Mentionable;
+use serenity::prelude::SerenityError;
+use serenity::utils;
 use std::iter;
 
+use command_error::CommandError;
 use connectionpool::*;
+use reminderqueue::ReminderQueue;
+use timeparse::{self, Recurrence};
 use util;
 
-static USAGE: &str = "Usage: `!remindme x scale`, where `x` is a number, \
-                      and scale is `minutes`, `hours`, `days` or `weeks`.";
+static USAGE: &str = "Usage: `!remindme time message`, where `time` is a duration like \
+                      `2h30m` or `1 day 4 hours`, a date like `2026-11-02 18:00`, \
+                      or something like `tomorrow 9am` or `next friday`.\n\
+                      Recurring reminders start with `every`, like `every monday 18:00`, \
+                      `every 2 weeks` or the cron expression `every 0 18 * * 1`, \
+                      and are stopped with `!remindme cancel id`.\n\
+                      Start with `here` to be reminded in this channel instead of by DM, \
+                      or `join` to let others sign up by reacting.\n\
+                      See your reminders with `!reminders`, and change one with \
+                      `!remindme edit id time` or `!remindme edit id message`.\n\
+                      `!reminders failed` lists the ones that couldn't be delivered.";
+
+static OTHERS_USAGE: &str = "Usage: `!remind @someone time message`, with any number of \
+                             users or roles to remind. See `!remindme` for the rest.";
+
+/// Reaction used to join someone else's reminder.
+const JOIN_EMOJI: char = '🔔';
+
+/// How many reminders `!reminders` shows, to stay below the message length limit.
+const MAX_LISTED: usize = 8;
+
+/// The longest reminder message, leaving room for the rest of the reminder
+/// within Discord's message length limit.
+const MAX_MESSAGE_CHARS: usize = 1500;
+
+/// How many times to try sending a reminder before giving up on it.
+/// Attempts are spaced 1, 2, 4, 8... minutes apart.
+const MAX_ATTEMPTS: i32 = 8;
+
+/// How often the reminder queue is reloaded from the database, in seconds.
+const RESYNC_INTERVAL_SECS: u64 = 60 * 60;
+
+/// How long delivered reminders are kept around before being deleted.
+const DELIVERED_RETENTION_DAYS: i32 = 30;
 
 command!(remind(ctx, msg, args) {
-    let num = args.single::<i64>().unwrap();
-    let scale = args.single::<String>().unwrap();
-
-    let mut message = String::new();
-    args.multiple::<String>()
-        .unwrap()
-        .into_iter()
-        .for_each(|s| {
-            message.push_str(&s);
-            message.push_str(" ");
-        });
+    let mut pool = util::get_pool(ctx);
+    let text = args.full();
 
-    let interval = match &*scale.to_lowercase() {
-        "minutes" | "minute" => Duration::minutes(num),
-        "hours" | "hour" => Duration::hours(num),
-        "days" | "day" => Duration::days(num),
-        "weeks" | "week" => Duration::weeks(num),
-        _ => {
-            util::print_or_log_error(&format!("Invalid duration scale.\n{}", USAGE), &msg.channel_id);
-            return Ok(());
+    if let Some(key) = strip_command(text, "cancel").or_else(|| strip_command(text, "stop")) {
+        let reply = if pool.delete_user_reminder(msg.author.id, key)? {
+            "Reminder cancelled.".to_owned()
+        } else {
+            unchanged_reply(&mut pool, msg.author.id, key)?
+        };
+        util::print_or_log_error(&reply, &msg.channel_id);
+        return Ok(());
+    }
+
+    if let Some(rest) = strip_command(text, "edit") {
+        let timezone = pool.get_timezone(msg.author.id)?;
+        let now = Utc::now().with_timezone(&timezone);
+        let queue = util::get_reminder_queue(ctx);
+        let reply = edit_reminder(&mut pool, &queue, msg.author.id, rest, &now)?;
+        util::print_or_log_error(&reply, &msg.channel_id);
+        return Ok(());
+    }
+
+    create_reminder(ctx, msg, text, vec![])?;
+});
+
+/// Reminds other members or roles instead of the author.
+command!(remind_others(ctx, msg, args) {
+    let mut text = args.full().trim_start();
+    let mut recipients = vec![];
+    loop {
+        let end = text.find(char::is_whitespace).unwrap_or(text.len());
+        let word = &text[..end];
+        if let Some(id) = utils::parse_role(word) {
+            recipients.push(Recipient::Role(RoleId(id)));
+        } else if let Some(id) = utils::parse_username(word) {
+            recipients.push(Recipient::User(UserId(id)));
+        } else {
+            break;
         }
-    };
+        text = text[end..].trim_start();
+    }
+
+    if recipients.is_empty() {
+        util::print_or_log_error(OTHERS_USAGE, &msg.channel_id);
+        return Ok(());
+    }
+    if let Some(why) = forbidden_mentions(msg, &recipients, args.full()) {
+        util::print_or_log_error(why, &msg.channel_id);
+        return Ok(());
+    }
+
+    create_reminder(ctx, msg, text, recipients)?;
+});
+
+/// Checks that the author of `!remind` may ping everyone they want reminded,
+/// so the bot can't be used to mention roles they couldn't mention themselves.
+/// Returns the reply for the user if they may not.
+fn forbidden_mentions(msg: &Message, recipients: &[Recipient], text: &str) -> Option<&'static str> {
+    let guild_id = msg.guild_id()?;
+    if text.contains("@everyone") || text.contains("@here")
+        || recipients.contains(&Recipient::Role(RoleId(guild_id.0)))
+    {
+        return Some("Reminders can't mention `@everyone` or `@here`.");
+    }
+
+    let can_mention_everyone = guild_id
+        .find()
+        .map_or(false, |g| g.read().permissions_in(msg.channel_id, msg.author.id).mention_everyone());
+    let unmentionable = recipients.iter().any(|r| match *r {
+        Recipient::Role(id) => !id.find().map_or(false, |role| role.mentionable),
+        Recipient::User(_) => false,
+    });
+    if unmentionable && !can_mention_everyone {
+        return Some("You can only remind roles that anyone can mention, \
+                     unless you're allowed to mention every role.");
+    }
+    None
+}
+
+/// Parses and stores a new reminder, then confirms it to the author.
+/// If `recipients` is empty, the author is the one reminded.
+fn create_reminder(
+    ctx: &Context,
+    msg: &Message,
+    text: &str,
+    mut recipients: Vec<Recipient>,
+) -> Result<(), CommandError> {
+    let mut pool = util::get_pool(ctx);
+    let timezone = pool.get_timezone(msg.author.id)?;
+    let now = Utc::now().with_timezone(&timezone);
 
-    let date = match Utc::now().naive_utc().checked_add_signed(interval) {
-        Some(v) => v,
-        None => {
-            util::print_or_log_error("Invalid date (overflow)", &msg.channel_id);
+    // `here` posts the reminder in this channel instead of a DM, and `join`
+    // lets others sign up for it by reacting.
+    let mut text = text;
+    let mut in_channel = false;
+    let mut joinable = false;
+    loop {
+        if let Some(rest) = strip_command(text, "here") {
+            in_channel = true;
+            text = rest;
+        } else if let Some(rest) = strip_command(text, "join") {
+            joinable = true;
+            text = rest;
+        } else {
+            break;
+        }
+    }
+
+    let (date, recurrence, message) = match parse_when(text, &now) {
+        Ok(v) => v,
+        Err(why) => {
+            util::print_or_log_error(&why, &msg.channel_id);
             return Ok(());
         }
     };
 
+    // Kept so reminders can still be looked up by bookmark like before jump links.
     let mut rng = thread_rng();
     let bookmark: String = iter::repeat(())
             .map(|()| rng.sample(Alphanumeric))
             .take(32)
             .collect();
 
-    util::get_pool(ctx).add_reminder(&msg.author.id, &msg.guild_id(), date, &message, &bookmark)?;
+    let id = pool.add_reminder(&NewReminder {
+        user_id: msg.author.id,
+        guild_id: msg.guild_id(),
+        channel_id: msg.channel_id,
+        message_id: msg.id,
+        date: date.naive_utc(),
+        message,
+        bookmark: &bookmark,
+        recurrence: recurrence.clone(),
+        in_channel,
+    })?;
+
+    // Once anyone joins, only recipients are reminded, so add the author.
+    if joinable && recipients.is_empty() {
+        recipients.push(Recipient::User(msg.author.id));
+    }
+    pool.add_reminder_recipients(id, &recipients)?;
+
+    let mut reply = match recurrence {
+        None => format!(
+            "Reminder `{}` set for {}.",
+            id,
+            date.format("%Y-%m-%d %H:%M %Z")
+        ),
+        Some(_) => format!(
+            "Recurring reminder `{}` set, first on {}.\n\
+             Stop it with `!remindme cancel {}`.",
+            id,
+            date.format("%Y-%m-%d %H:%M %Z"),
+            id
+        ),
+    };
+    util::get_reminder_queue(ctx).push(date.naive_utc());
+
+    if !joinable {
+        util::print_or_log_error(&reply, &msg.channel_id);
+        return Ok(());
+    }
+
+    reply.push_str(&format!("\nReact with {} to be reminded as well.", JOIN_EMOJI));
+    let confirmation = msg.channel_id.say(&reply)?;
+    confirmation.react(JOIN_EMOJI)?;
+    pool.set_reminder_join_message(id, confirmation.id)?;
+
+    Ok(())
+}
+
+/// Adds or removes someone reacting to the confirmation of a joinable reminder.
+pub fn join_by_reaction(ctx: &Context, reaction: &Reaction, joined: bool) {
+    if reaction.emoji != ReactionType::from(JOIN_EMOJI) || reaction.user_id == CACHE.read().user.id {
+        return;
+    }
+
+    let mut pool = util::get_pool(ctx);
+    let result = if joined {
+        pool.join_reminder(reaction.message_id, reaction.user_id, Utc::now().naive_utc())
+    } else {
+        pool.leave_reminder(reaction.message_id, reaction.user_id)
+    };
+    if let Err(why) = result {
+        error!("Failed to update reminder recipients: {}", why);
+    }
+}
+
+/// Lists the pending reminders of the author, or with `failed` the ones that
+/// could not be delivered.
+command!(reminders(ctx, msg, args) {
+    if strip_command(args.full(), "failed").is_some() {
+        let failed = util::get_pool(ctx).get_user_failed_reminders(msg.author.id, MAX_LISTED as i64)?;
+        let reply = if failed.is_empty() {
+            "None of your reminders failed to be delivered.".to_owned()
+        } else {
+            failed.iter().map(describe_failed_reminder).collect::<Vec<_>>().join("\n")
+        };
+        util::print_or_log_error(&reply, &msg.channel_id);
+        return Ok(());
+    }
+
+    let reminders = util::get_pool(ctx).get_user_reminders(msg.author.id)?;
+    if reminders.is_empty() {
+        util::print_or_log_error("You have no pending reminders.", &msg.channel_id);
+        return Ok(());
+    }
+
+    let mut result = reminders
+        .iter()
+        .take(MAX_LISTED)
+        .map(describe_reminder)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if reminders.len() > MAX_LISTED {
+        result.push_str(&format!("\n...and {} more.", reminders.len() - MAX_LISTED));
+    }
 
-    util::print_or_log_error(&format!(
-        "Reminder set for {} UTC.\nBookmark: `{}`",
-        date.format("%Y-%m-%d %H:%M"),
-        bookmark
-    ), &msg.channel_id);
+    util::print_or_log_error(&result, &msg.channel_id);
 });
 
-/// Infinite loop that checks the database periodically for expired reminders.
-pub fn watch_for_reminders(mut pool: ConnectionPool) -> ! {
+/// One line summary of a reminder for `!reminders`.
+fn describe_reminder(reminder: &Reminder) -> String {
+    let mut result = format!(
+        "`{}` {}",
+        reminder.id,
+        reminder.timezone.from_utc_datetime(&reminder.date).format("%Y-%m-%d %H:%M %Z")
+    );
+
+    if let Some(ref recurrence) = reminder.recurrence {
+        result.push_str(&format!(", repeating {}", recurrence.describe()));
+    }
+
+    let guild_id = reminder.guild_id.as_ref().and_then(|s| u64::from_str(s).ok());
+    if let Some(guild_id) = guild_id {
+        result.push_str(&format!(" in {}", util::guild_name(GuildId(guild_id))));
+    }
+
+    match reminder.message {
+        Some(ref m) if !m.trim().is_empty() => result.push_str(&format!(": {}", m.trim())),
+        _ => result.push_str(": (no message)"),
+    }
+
+    result
+}
+
+/// One line summary of a reminder for `!reminders failed`.
+fn describe_failed_reminder(failed: &FailedReminder) -> String {
+    let message = match failed.message {
+        Some(ref m) if !m.trim().is_empty() => m.trim(),
+        _ => "(no message)",
+    };
+    let repeating = match failed.recurrence {
+        Some(ref recurrence) => format!(", repeating {}", recurrence.describe()),
+        None => String::new(),
+    };
+    format!(
+        "`{}` due {}{}, not delivered: {} ({})",
+        failed.id,
+        failed.timezone.from_utc_datetime(&failed.date).format("%Y-%m-%d %H:%M %Z"),
+        repeating,
+        message,
+        failed.error
+    )
+}
+
+/// Handles `!remindme edit id ...`, changing the time of the reminder if the
+/// rest of the text starts with one, and the message otherwise.
+/// Returns the reply for the user.
+fn edit_reminder(
+    pool: &mut ConnectionPool,
+    queue: &ReminderQueue,
+    user_id: UserId,
+    text: &str,
+    now: &DateTime<Tz>,
+) -> Result<String, CommandError> {
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let (key, rest) = (&text[..end], text[end..].trim());
+    if key.is_empty() || rest.is_empty() {
+        return Ok(USAGE.to_owned());
+    }
+
+    let is_time = timeparse::parse(rest, now.naive_local()).is_ok()
+        || timeparse::parse_recurrence(rest).map_or(false, |r| r.is_some());
+    if !is_time {
+        let rest = match check_length(rest) {
+            Ok(rest) => rest,
+            Err(why) => return Ok(why),
+        };
+        return Ok(if pool.update_user_reminder_message(user_id, key, rest)? {
+            "Reminder text changed.".to_owned()
+        } else {
+            unchanged_reply(pool, user_id, key)?
+        });
+    }
+
+    let (date, recurrence, message) = match parse_when(rest, now) {
+        Ok(v) => v,
+        Err(why) => return Ok(why),
+    };
+    // A new time alone keeps how the reminder repeats, and its text.
+    let message = if message.is_empty() { None } else { Some(message) };
+    if !pool.update_user_reminder_date(user_id, key, date.naive_utc(), &recurrence, message)? {
+        return unchanged_reply(pool, user_id, key);
+    }
+    queue.push(date.naive_utc());
+
+    Ok(format!("Reminder moved to {}.", date.format("%Y-%m-%d %H:%M %Z")))
+}
+
+/// Explains why a reminder could not be cancelled or edited.
+fn unchanged_reply(pool: &mut ConnectionPool, user_id: UserId, key: &str) -> Result<String, CommandError> {
+    Ok(if pool.is_user_reminder_sending(user_id, key)? {
+        "That reminder is being delivered right now, try again in a moment.".to_owned()
+    } else {
+        "You have no reminder with that id or bookmark.".to_owned()
+    })
+}
+
+/// Returns the rest of `text` if it starts with the word `command`.
+fn strip_command<'a>(text: &'a str, command: &str) -> Option<&'a str> {
+    let text = text.trim_start();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    if text[..end].eq_ignore_ascii_case(command) {
+        Some(text[end..].trim())
+    } else {
+        None
+    }
+}
+
+/// Works out when a reminder is first due, how it repeats and what its
+/// message is. Errors are returned as a reply for the user.
+fn parse_when<'a>(
+    text: &'a str,
+    now: &DateTime<Tz>,
+) -> Result<(DateTime<Tz>, Option<Recurrence>, &'a str), String> {
+    if let Some((recurrence, message)) = timeparse::parse_recurrence(text)
+        .map_err(|why| format!("{}\n{}", why, USAGE))?
+    {
+        let first = recurrence.next_after(now);
+        let second = first.as_ref().and_then(|first| recurrence.next_after(first));
+        return match (first, second) {
+            (Some(first), Some(second)) => {
+                if second.signed_duration_since(first.clone()) < Duration::hours(1) {
+                    Err("Reminders can't repeat more often than once an hour.".to_owned())
+                } else {
+                    Ok((first, Some(recurrence), check_length(message)?))
+                }
+            }
+            _ => Err("That schedule never comes around.".to_owned()),
+        };
+    }
+
+    let (when, message) = timeparse::parse(text, now.naive_local())
+        .map_err(|why| format!("{}\n{}", why, USAGE))?;
+
+    let date = when
+        .resolve(now)
+        .ok_or_else(|| "Invalid date (overflow or skipped by daylight saving)".to_owned())?;
+
+    if date <= *now {
+        return Err("That time has already passed.".to_owned());
+    }
+
+    Ok((date, None, check_length(message)?))
+}
+
+/// Checks that a reminder message isn't too long to be sent later.
+fn check_length(message: &str) -> Result<&str, String> {
+    if message.chars().count() > MAX_MESSAGE_CHARS {
+        Err(format!("Reminders can be at most {} characters long.", MAX_MESSAGE_CHARS))
+    } else {
+        Ok(message)
+    }
+}
+
+/// Infinite loop that sends reminders as they fall due.
+pub fn watch_for_reminders(mut pool: ConnectionPool, queue: ReminderQueue) -> ! {
+    // Anything that was being sent when the bot stopped is sent again.
+    if let Err(why) = pool.reset_sending_reminders() {
+        error!("Failed to reset reminders: {:?}", why);
+    }
+    resync_queue(&mut pool, &queue);
+
     loop {
-        thread::sleep(time::Duration::from_secs(60));
+        // Reload the queue from the database every now and then, in case
+        // a reminder was added without going through the queue.
+        if !queue.wait_until_due(time::Duration::from_secs(RESYNC_INTERVAL_SECS)) {
+            resync_queue(&mut pool, &queue);
+            continue;
+        }
 
         // Get expired reminders.
-        let reminders = match pool.get_expired_reminders() {
+        let now = Utc::now().naive_utc();
+        let reminders = match pool.claim_due_reminders(now) {
             Ok(rows) => rows,
             Err(why) => {
                 error!("Failed to get reminders: {:?}", why);
+                queue.push(now + Duration::minutes(1));
                 continue;
             }
         };
 
-        // Delete the reminder no matter if the reminder was sent sucessfully
-        // or not to avoid retrying to message deleted accounts forever.
-        for reminder in reminders.iter() {
-            if let Err(why) = pool.delete_reminder(reminder.id) {
-                warn!("Failed to delete reminder: {}", why);
+        for reminder in reminders.into_iter() {
+            deliver(&mut pool, &queue, reminder);
+        }
+
+        if let Err(why) = pool.purge_delivered_reminders(DELIVERED_RETENTION_DAYS) {
+            warn!("Failed to purge delivered reminders: {}", why);
+        }
+    }
+}
+
+fn resync_queue(pool: &mut ConnectionPool, queue: &ReminderQueue) {
+    if let Err(why) = queue.resync(|| pool.get_reminder_due_times()) {
+        error!("Failed to load reminders: {:?}", why);
+        queue.push(Utc::now().naive_utc() + Duration::minutes(1));
+    }
+}
+
+/// Sends a claimed reminder and records the outcome, rescheduling it,
+/// retrying it later or giving up on it.
+fn deliver(pool: &mut ConnectionPool, queue: &ReminderQueue, reminder: Reminder) {
+    let id = reminder.id;
+    let error = match send_reminder(pool, &reminder) {
+        Ok(()) => {
+            let result = match next_occurrence(&reminder) {
+                Some(next) => {
+                    queue.push(next);
+                    pool.reschedule_reminder(id, next)
+                }
+                None => pool.mark_reminder_delivered(id),
             };
+            if let Err(why) = result {
+                error!("Failed to mark reminder {} as delivered: {}", id, why);
+            }
+            return;
         }
+        Err(why) => why,
+    };
 
-        // Send all reminders.
-        for reminder in reminders.into_iter() {
-            if let Err(why) = dm_with_message(reminder) {
-                error!("Error while DM'ing: {}", why);
+    let attempts = reminder.attempts + 1;
+    let result = if error.permanent || attempts >= MAX_ATTEMPTS {
+        // A recurring reminder only gives up on this occurrence, so that one
+        // failure doesn't stop the whole series.
+        match next_occurrence(&reminder) {
+            Some(next) => {
+                error!("Skipping occurrence of reminder {} after {} attempts: {}",
+                       id, attempts, error.message);
+                queue.push(next);
+                pool.skip_failed_occurrence(id, attempts, &error.message, next)
+            }
+            None => {
+                error!("Giving up on reminder {} after {} attempts: {}", id, attempts, error.message);
+                pool.dead_letter_reminder(id, attempts, &error.message)
             }
         }
+    } else {
+        let delay = Duration::minutes(1 << (attempts - 1));
+        warn!("Failed to send reminder {}, retrying in {} minutes: {}",
+              id, delay.num_minutes(), error.message);
+        let retry_at = Utc::now().naive_utc() + delay;
+        queue.push(retry_at);
+        pool.retry_reminder(id, attempts, retry_at)
+    };
+    if let Err(why) = result {
+        error!("Failed to record failure of reminder {}: {}", id, why);
     }
 }
 
-/// Parses a user_id and sends a reminder to the user.
-fn dm_with_message(reminder: Reminder) -> Result<(), String> {
-    let userid = UserId::from_str(&reminder.user_id).map_err(|e| format!("Failed to get user id: {}", e))?;
+/// Finds the next time a recurring reminder is due, skipping any occurrences
+/// that were missed while the bot was down.
+fn next_occurrence(reminder: &Reminder) -> Option<NaiveDateTime> {
+    let recurrence = reminder.recurrence.as_ref()?;
+    let now = Utc::now().with_timezone(&reminder.timezone);
 
-    let user = userid
-        .get()
-        .map_err(|e| format!("Failed to get user: {}", e))?;
+    let mut next = reminder.timezone.from_utc_datetime(&reminder.date);
+    while next <= now {
+        next = recurrence.next_after(&next)?;
+    }
 
+    Some(next.naive_utc())
+}
+
+/// Why a reminder could not be sent.
+struct DeliveryError {
+    message: String,
+    /// Whether trying again is pointless, e.g. for deleted users or closed DMs.
+    permanent: bool,
+}
+
+impl DeliveryError {
+    fn new(message: String, permanent: bool) -> DeliveryError {
+        DeliveryError { message, permanent }
+    }
+
+    /// Classifies an error from Discord. Not found and forbidden responses
+    /// won't change by trying again, anything else might.
+    fn from_serenity(context: &str, err: SerenityError) -> DeliveryError {
+        let permanent = match err {
+            SerenityError::Http(HttpError::UnsuccessfulRequest(ref response)) => {
+                response.status == StatusCode::NotFound || response.status == StatusCode::Forbidden
+            }
+            _ => false,
+        };
+        DeliveryError::new(format!("{}: {}", context, err), permanent)
+    }
+}
+
+/// Sends a reminder as a DM, or in the channel it was created in if the user
+/// asked for that or can't be DM'ed.
+fn send_reminder(pool: &mut ConnectionPool, reminder: &Reminder) -> Result<(), DeliveryError> {
+    let owner = UserId::from_str(&reminder.user_id)
+        .map_err(|e| DeliveryError::new(format!("Failed to get user id: {}", e), true))?;
+    let recipients = pool.get_reminder_recipients(reminder.id)
+        .map_err(|e| DeliveryError::new(format!("Failed to get recipients: {}", e), false))?;
+
+    if recipients.is_empty() {
+        let response = reminder_text(reminder, None);
+        if reminder.in_channel {
+            return post_in_channel(reminder, &owner.mention(), &response);
+        }
+
+        return match dm_with_message(owner, &response) {
+            Ok(()) => Ok(()),
+            Err(why) if reminder.channel_id.is_some() => {
+                warn!("{}, posting reminder {} in its channel instead.", why.message, reminder.id);
+                post_in_channel(reminder, &owner.mention(), &response)
+            }
+            Err(why) => Err(why),
+        };
+    }
+
+    // Roles, and users who can't be DM'ed, are mentioned in the channel instead.
+    let response = reminder_text(reminder, Some(owner));
+    let mut mentions = vec![];
+    for recipient in recipients {
+        match recipient {
+            Recipient::Role(id) => mentions.push(id.mention()),
+            Recipient::User(id) if reminder.in_channel => mentions.push(id.mention()),
+            Recipient::User(id) => {
+                if let Err(why) = dm_with_message(id, &response) {
+                    warn!("{}, mentioning them for reminder {} instead.", why.message, reminder.id);
+                    mentions.push(id.mention());
+                }
+            }
+        }
+    }
+
+    if mentions.is_empty() {
+        return Ok(());
+    }
+    post_in_channel(reminder, &mentions.join(" "), &response)
+}
+
+/// Writes the text of a reminder. `from` is who set it, if it's for someone else.
+fn reminder_text(reminder: &Reminder, from: Option<UserId>) -> String {
+    let date = reminder.timezone
+        .from_utc_datetime(&reminder.date)
+        .format("%Y-%m-%d %H:%M %Z");
+    let who = match from {
+        Some(user) => format!("{} asked me", user.mention()),
+        None => "You asked me".to_owned(),
+    };
     let mut response = match reminder.message {
-        None => "Hello! You asked me to remind you of something at this time,\
-         but you didn't specify what!".to_owned(),
-        Some(m) => format!("Hello! You asked me to remind you of the following:\n{}", m)
+        Some(ref m) if !m.trim().is_empty() => {
+            format!("Hello! {} to remind you of the following at {}:\n{}", who, date, m)
+        }
+        _ => format!("Hello! {} to remind you of something at {}, \
+         but didn't specify what!", who, date),
     };
 
-    response.push_str(&format!("\nYou can find the place you issued the command\
-                              by searching for `{}`", reminder.bookmark));
-
-    if let Some(server_id) = reminder.server_id {
-        let servername = u64::from_str(&server_id)
-            .and_then(|u| Ok(GuildId::from(u)))
-            .map_err(|e| format!("Failed to get user id: {}", e))?;
+    let guild_id = reminder.guild_id
+        .as_ref()
+        .and_then(|s| u64::from_str(s).ok())
+        .map(GuildId);
+    let guild = guild_id.map(|g| format!(" in {}", util::guild_name(g))).unwrap_or_default();
 
-        response.push_str(&format!(" in {}", servername));
+    match (&reminder.channel_id, &reminder.message_id) {
+        (&Some(ref channel_id), &Some(ref message_id)) => {
+            let link_guild = guild_id.map_or("@me".to_owned(), |g| g.0.to_string());
+            response.push_str(&format!(
+                "\nOriginal message{}: https://discord.com/channels/{}/{}/{}",
+                guild, link_guild, channel_id, message_id
+            ));
+        }
+        // Reminders from before jump links only have a bookmark to search for.
+        _ => response.push_str(&format!(
+            "\nYou can find the place you issued the command by searching for `{}`{}",
+            reminder.bookmark, guild
+        )),
     }
 
-    user.direct_message(|m| m.content(&response))
-        .map_err(|why| format!("Failed to DM user: {}", why))?;
+    response
+}
+
+/// Sends a direct message to the user.
+fn dm_with_message(userid: UserId, response: &str) -> Result<(), DeliveryError> {
+    let user = userid
+        .get()
+        .map_err(|e| DeliveryError::from_serenity("Failed to get user", e))?;
+
+    user.direct_message(|m| m.content(response))
+        .map_err(|why| DeliveryError::from_serenity("Failed to DM user", why))?;
+
+    Ok(())
+}
+
+/// Posts the reminder in the channel it was created in, after `mentions`.
+fn post_in_channel(reminder: &Reminder, mentions: &str, response: &str) -> Result<(), DeliveryError> {
+    let channel_id = reminder.channel_id
+        .as_ref()
+        .ok_or_else(|| "Reminder has no channel".to_owned())
+        .and_then(|c| u64::from_str(c).map_err(|e| format!("Failed to get channel id: {}", e)))
+        .map_err(|e| DeliveryError::new(e, true))?;
+
+    ChannelId(channel_id)
+        .say(format!("{} {}", mentions, response))
+        .map_err(|why| DeliveryError::from_serenity("Failed to post in channel", why))?;
 
     Ok(())
 }
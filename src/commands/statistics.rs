@@ -1,48 +1,167 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use chrono::offset::{TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json;
+use std::cmp;
+use std::collections::HashMap;
+use serenity::client::Context;
 use serenity::framework::standard::CommandError;
+use serenity::model::channel::{Message, MessageType};
+use serenity::model::id::{ChannelId, UserId};
+use serenity::utils;
 
+use super::emoji;
+use super::leaderboard::{self, Leaderboard, MAX_PAGE_SIZE};
+use super::members;
+use super::timezone::find_timezone;
+use super::voice;
+use chart;
+use connectionpool::{
+    DailyStatistics, EmojiDelta, EmojiKey, LifetimeStatistics, StatisticsKey, StatisticsOrder,
+    UserDailyStatistics,
+};
 use util;
 
-/// Get the top ten most active users by word count.
-/// Default number of days of activity to look at is 7.
+/// The most days `?stats @user` and the other detailed statistics look at.
+pub const MAX_DAYS: u32 = 365;
+
+/// How many days `?stats heatmap` looks at by default.
+const HEATMAP_DAYS: u32 = 28;
+
+/// How many days `?stats export` exports by default.
+const EXPORT_DAYS: u32 = 30;
+
+/// Up to how many days `?stats @user` lists in a table.
+const MAX_TABLE_DAYS: usize = 14;
+
+/// How many days go on one line of a sparkline.
+const SPARKLINE_WIDTH: usize = 50;
+
+/// Counts a message and its words towards the statistics of its author, and
+/// the custom emoji in it.
+/// Messages outside guilds, and from bots, webhooks or the system, don't count.
+pub fn record_message(ctx: &Context, msg: &Message) {
+    if msg.author.bot || msg.webhook_id.is_some() || msg.kind != MessageType::Regular {
+        return;
+    }
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return,
+    };
+
+    let sent = msg.timestamp.naive_utc();
+    let key = StatisticsKey {
+        guild_id,
+        channel_id: msg.channel_id,
+        user_id: msg.author.id,
+        hour: sent.date().and_hms(sent.hour(), 0, 0),
+    };
+    let aggregator = util::get_stats_aggregator(ctx);
+    aggregator.record(key, msg.content.split_whitespace().count() as i32);
+    aggregator.record_username(msg.author.id, &msg.author.name);
+    for emoji_id in emoji::custom_emoji(&msg.content) {
+        let key = EmojiKey { guild_id, emoji_id, date: sent.date() };
+        aggregator.record_emoji(key, EmojiDelta { messages: 1, reactions: 0 });
+    }
+    let config = util::get_level_config(ctx);
+    aggregator.record_xp(guild_id, msg.author.id, config.xp_per_message, sent, config.cooldown);
+}
+
+/// Get the most active users by word count, optionally in one channel, sorted
+/// by messages or words per message instead, and a page at a time,
+/// or the daily activity of one user if they're mentioned.
+/// `?stats chart` draws the activity instead, `?stats voice` lists time spent
+/// in voice channels and `?stats members` shows how many joined and left.
 command!(stats(ctx, msg, args) {
-    let days = args.single::<u32>().unwrap_or(7);
+    let text = args.full().trim();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    match text[..end].to_lowercase().as_str() {
+        "chart" => return draw_chart(ctx, msg, &StatsOptions::parse(&text[end..])),
+        "heatmap" => return draw_heatmap(ctx, msg, &StatsOptions::parse(&text[end..])),
+        "export" => return export(ctx, msg, &text[end..]),
+        "voice" => return Ok(voice::stats(ctx, msg, &text[end..])?),
+        "members" => return Ok(members::stats(ctx, msg, &text[end..])?),
+        _ => {}
+    }
+
+    let options = StatsOptions::parse(text);
+    let days = cmp::min(cmp::max(options.days.unwrap_or(util::DEFAULT_DAYS), 1), MAX_DAYS);
     let mut pool = util::get_pool(ctx);
-    
-    let guild = msg.guild().unwrap();
-    let guild = guild.read();
 
-    // Get the stats.
-    let statistics = match pool.get_statistics(guild.id, days) {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    if let Some(user_id) = options.user_id {
+        let since = util::first_day(&mut pool, msg.author.id, days)?;
+        let until = since + Duration::days(i64::from(days) - 1);
+        let rolled_up = pool.get_rolled_up_until(guild_id)?.filter(|&date| date > since);
+        let daily_since = rolled_up.unwrap_or(since);
+        let daily = pool.get_user_daily_statistics(guild_id, user_id, daily_since)?;
+        let totals = pool.get_user_totals(guild_id, user_id, since)?;
+        let rank = pool.get_user_rank(guild_id, user_id, since)?;
+        let lifetime = pool.get_lifetime_statistics(guild_id, user_id, Utc::now().naive_utc().date())?;
+        let name = util::member_name(guild_id, user_id, pool.get_username(user_id)?.as_ref());
+
+        util::print_or_log_error(&format!(
+            "Activity of {} the last {} days (since {}):\n```\n{}\n```",
+            name, days, since.format("%Y-%m-%d"),
+            describe_user_activity(
+                &fill_days(&daily, daily_since, until), totals, days, rank, &lifetime, rolled_up
+            )
+        ), &msg.channel_id);
+        return Ok(());
+    }
+
+    let since = util::first_day(&mut pool, msg.author.id, days)?;
+    let count = options.count.unwrap_or(10);
+    leaderboard::post(ctx, msg, Leaderboard {
+        guild_id: guild_id,
+        author: msg.author.id,
+        days,
+        since,
+        channel_id: options.channel_id,
+        order: options.order.unwrap_or(StatisticsOrder::Words),
+        page_size: cmp::min(cmp::max(count, 1), MAX_PAGE_SIZE),
+        page: cmp::max(options.page.unwrap_or(1), 1),
+    })?;
+});
+
+/// Get the ten most active channels by message count.
+command!(channelstats(ctx, msg, args) {
+    let days = cmp::min(cmp::max(args.single::<u32>().unwrap_or(util::DEFAULT_DAYS), 1), MAX_DAYS);
+    let mut pool = util::get_pool(ctx);
+
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let since = util::first_day(&mut pool, msg.author.id, days)?;
+
+    let statistics = match pool.get_channel_statistics(guild_id, since) {
         Ok(s) => s,
         Err(e) => {
-            error!("Failed to get statistics: {}", e);
+            error!("Failed to get channel statistics: {}", e);
             return Err(CommandError("Database connection error.".to_owned()));
         }
     };
 
-    // Pretty print.
-    let name_width = statistics
-        .iter()
-        .map(|ref s| s.user_id.display_name().len())
-        .max()
-        .unwrap_or(5);
-    let words_width = statistics
-        .iter()
-        .map(|ref s| util::digits(s.words))
-        .max()
-        .unwrap_or(5);
-    let messages_width = statistics
+    let names: Vec<String> = statistics
         .iter()
-        .map(|ref s| util::digits(s.messages))
-        .max()
-        .unwrap_or(5);
+        .map(|s| format!("#{}", util::channel_name(s.channel_id)))
+        .collect();
+    let name_width = names.iter().map(|n| n.len()).max().unwrap_or(5);
+    let messages_width = statistics.iter().map(|s| util::digits(s.messages)).max().unwrap_or(5);
+    let words_width = statistics.iter().map(|s| util::digits(s.words)).max().unwrap_or(5);
 
     let result = statistics
-        .into_iter()
-        .map(|s| {
+        .iter()
+        .zip(names.iter())
+        .map(|(s, name)| {
             format!(
                 "{:<nw$} | {:>mw$} messages | {:>ww$} words.",
-                s.user_id.display_name(),
+                name,
                 s.messages,
                 s.words,
                 nw = name_width,
@@ -53,8 +172,429 @@ command!(stats(ctx, msg, args) {
         .fold(String::new(), |a, b| format!("{}\n{}", a, b));
 
     util::print_or_log_error(&format!(
-        "Statistics for the top 10 most active users the last {} days:\
+        "Statistics for the top 10 most active channels the last {} days (since {}):\
          \n```\n{}\n```",
-        days, result
+        days, since.format("%Y-%m-%d"), result
     ), &msg.channel_id);
 });
+
+/// Options accepted by `?stats` and its subcommands, in any order.
+struct StatsOptions {
+    days: Option<u32>,
+    channel_id: Option<ChannelId>,
+    user_id: Option<UserId>,
+    timezone: Option<Tz>,
+    order: Option<StatisticsOrder>,
+    count: Option<i64>,
+    page: Option<i64>,
+}
+
+impl StatsOptions {
+    fn parse(text: &str) -> StatsOptions {
+        let mut options = StatsOptions {
+            days: None,
+            channel_id: None,
+            user_id: None,
+            timezone: None,
+            order: None,
+            count: None,
+            page: None,
+        };
+        let mut args = text.split_whitespace().peekable();
+        while let Some(arg) = args.next() {
+            // The number after `top` or `page` isn't a number of days.
+            let number = args.peek().and_then(|n| n.parse::<i64>().ok());
+            match (arg.to_lowercase().as_str(), number) {
+                ("top", Some(n)) => {
+                    options.count = Some(n);
+                    args.next();
+                    continue;
+                }
+                ("page", Some(n)) => {
+                    options.page = Some(n);
+                    args.next();
+                    continue;
+                }
+                ("messages", _) => {
+                    options.order = Some(StatisticsOrder::Messages);
+                    continue;
+                }
+                ("words", _) => {
+                    options.order = Some(StatisticsOrder::Words);
+                    continue;
+                }
+                ("wpm", _) | ("words-per-message", _) => {
+                    options.order = Some(StatisticsOrder::WordsPerMessage);
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let Ok(n) = arg.parse::<u32>() {
+                options.days = Some(n);
+            } else if let Some(id) = utils::parse_channel(arg) {
+                options.channel_id = Some(ChannelId(id));
+            } else if let Some(id) = utils::parse_username(arg) {
+                options.user_id = Some(UserId(id));
+            } else if let Some(tz) = find_timezone(arg) {
+                options.timezone = Some(tz);
+            }
+        }
+        options
+    }
+}
+
+/// Uploads a chart of the messages per day in the guild, or of one user.
+fn draw_chart(ctx: &Context, msg: &Message, options: &StatsOptions) -> Result<(), CommandError> {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let mut pool = util::get_pool(ctx);
+
+    let days = cmp::min(cmp::max(options.days.unwrap_or(util::DEFAULT_DAYS), 1), MAX_DAYS);
+    let since = util::first_day(&mut pool, msg.author.id, days)?;
+    let until = since + Duration::days(i64::from(days) - 1);
+    let rolled_up = pool.get_rolled_up_until(guild_id)?.filter(|&date| date > since);
+    let since = rolled_up.unwrap_or(since);
+    if since > until {
+        util::print_or_log_error(&format!(
+            "Only monthly totals are kept from before {}, so there are no days to chart.",
+            since.format("%Y-%m-%d")
+        ), &msg.channel_id);
+        return Ok(());
+    }
+
+    let (daily, whose) = match options.user_id {
+        Some(user_id) => {
+            let name = util::member_name(guild_id, user_id, pool.get_username(user_id)?.as_ref());
+            (pool.get_user_daily_statistics(guild_id, user_id, since)?, name)
+        }
+        None => (pool.get_guild_daily_statistics(guild_id, since)?, util::guild_name(guild_id)),
+    };
+
+    let title = format!("Messages per day by {} since {}", whose, since.format("%Y-%m-%d"));
+    let png = chart::activity_chart(&title, &fill_days(&daily, since, until))?;
+    msg.channel_id.send_files(vec![(&png[..], "activity.png")], |m| match rolled_up {
+        Some(date) => m.content(&format!(
+            "Only monthly totals are kept from before {}, so the chart starts there.",
+            date.format("%Y-%m-%d")
+        )),
+        None => m,
+    })?;
+
+    Ok(())
+}
+
+/// Uploads a heatmap of when the guild is active per weekday and hour, in
+/// the given timezone or that of the author.
+fn draw_heatmap(ctx: &Context, msg: &Message, options: &StatsOptions) -> Result<(), CommandError> {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let mut pool = util::get_pool(ctx);
+
+    let timezone = match options.timezone {
+        Some(tz) => tz,
+        None => pool.get_timezone(msg.author.id)?,
+    };
+    let days = cmp::min(cmp::max(options.days.unwrap_or(HEATMAP_DAYS), 1), MAX_DAYS);
+    let since = Utc::now().naive_utc() - Duration::days(i64::from(days));
+    let hours = pool.get_hourly_statistics(guild_id, since)?;
+
+    let title = format!(
+        "When {} is active ({}, last {} days)",
+        util::guild_name(guild_id), timezone.name(), days
+    );
+    let png = chart::heatmap(&title, &heatmap_grid(&hours, timezone))?;
+    msg.channel_id.send_files(vec![(&png[..], "heatmap.png")], |m| m)?;
+
+    Ok(())
+}
+
+/// Sums up messages per UTC hour into weekdays, starting on monday, and hours
+/// in `timezone`.
+fn heatmap_grid(hours: &[(NaiveDateTime, i64)], timezone: Tz) -> [[i64; 24]; 7] {
+    let mut grid = [[0; 24]; 7];
+    for &(hour, messages) in hours {
+        let local = timezone.from_utc_datetime(&hour);
+        grid[local.weekday().num_days_from_monday() as usize][local.hour() as usize] += messages;
+    }
+    grid
+}
+
+/// Uploads the messages and words of every user per day as CSV or JSON.
+fn export(ctx: &Context, msg: &Message, text: &str) -> Result<(), CommandError> {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let options = StatsOptions::parse(text);
+    let json = text.split_whitespace().any(|arg| arg.eq_ignore_ascii_case("json"));
+    let mut pool = util::get_pool(ctx);
+
+    let days = cmp::min(cmp::max(options.days.unwrap_or(EXPORT_DAYS), 1), MAX_DAYS);
+    let since = util::first_day(&mut pool, msg.author.id, days)?;
+    let rolled_up = pool.get_rolled_up_until(guild_id)?.filter(|&date| date > since);
+    let statistics = pool.get_users_daily_statistics(guild_id, rolled_up.unwrap_or(since))?;
+
+    let mut names: HashMap<UserId, String> = HashMap::new();
+    for s in &statistics {
+        names
+            .entry(s.user_id)
+            .or_insert_with(|| util::member_name(guild_id, s.user_id, s.username.as_ref()));
+    }
+    let (file, name) = if json {
+        (export_json(&statistics, &names), "statistics.json")
+    } else {
+        (export_csv(&statistics, &names), "statistics.csv")
+    };
+
+    let mut content = format!("Statistics for the last {} days (since {}).", days, since.format("%Y-%m-%d"));
+    if let Some(date) = rolled_up {
+        content.push_str(&format!(
+            " Only monthly totals are kept from before {}, so those days are left out.",
+            date.format("%Y-%m-%d")
+        ));
+    }
+    msg.channel_id.send_files(vec![(file.as_bytes(), name)], |m| m.content(&content))?;
+
+    Ok(())
+}
+
+fn export_csv(statistics: &[UserDailyStatistics], names: &HashMap<UserId, String>) -> String {
+    let mut csv = "user_id,display_name,date,messages,words\n".to_owned();
+    for s in statistics {
+        let name = names.get(&s.user_id).map_or("", |n| n.as_str());
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            s.user_id, csv_field(name), s.date.format("%Y-%m-%d"), s.messages, s.words
+        ));
+    }
+    csv
+}
+
+/// Quotes a CSV field if it contains anything that would break the row.
+/// Fields that spreadsheets would run as a formula get a `'` in front.
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(|c: char| "=+-@\t\r".contains(c)) {
+        format!("'{}", field)
+    } else {
+        field.to_owned()
+    };
+    if field.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+fn export_json(statistics: &[UserDailyStatistics], names: &HashMap<UserId, String>) -> String {
+    let rows: Vec<_> = statistics
+        .iter()
+        .map(|s| json!({
+            "user_id": format!("{}", s.user_id),
+            "display_name": names.get(&s.user_id),
+            "date": s.date.format("%Y-%m-%d").to_string(),
+            "messages": s.messages,
+            "words": s.words,
+        }))
+        .collect();
+    serde_json::to_string_pretty(&rows).unwrap_or_default()
+}
+
+/// Summarises the activity of a user over `days` days, with a table of the
+/// days if there are few enough, and a sparkline of the messages, followed by
+/// their activity since they were first counted. `daily` only has the days
+/// from `rolled_up` on if the days before it are only kept as monthly totals,
+/// which still count in `totals`.
+fn describe_user_activity(
+    daily: &[DailyStatistics],
+    totals: (i64, i64),
+    days: u32,
+    rank: Option<(i64, i64)>,
+    lifetime: &LifetimeStatistics,
+    rolled_up: Option<NaiveDate>,
+) -> String {
+    let (messages, words) = totals;
+    let days = f64::from(cmp::max(days, 1));
+
+    let mut lines = vec![];
+    if let Some(date) = rolled_up {
+        lines.push(format!(
+            "Only monthly totals are kept from before {}, so those days aren't shown.",
+            date.format("%Y-%m-%d")
+        ));
+        lines.push(String::new());
+    }
+    if daily.len() <= MAX_TABLE_DAYS {
+        let messages_width = daily.iter().map(|d| util::digits(d.messages)).max().unwrap_or(1);
+        let words_width = daily.iter().map(|d| util::digits(d.words)).max().unwrap_or(1);
+        for day in daily {
+            lines.push(format!(
+                "{} | {:>mw$} messages | {:>ww$} words",
+                day.date.format("%a %Y-%m-%d"),
+                day.messages,
+                day.words,
+                mw = messages_width,
+                ww = words_width
+            ));
+        }
+        lines.push(String::new());
+    }
+
+    let values: Vec<i64> = daily.iter().map(|d| d.messages).collect();
+    for chunk in values.chunks(SPARKLINE_WIDTH) {
+        lines.push(sparkline(chunk, values.iter().cloned().max().unwrap_or(0)));
+    }
+    lines.push(String::new());
+
+    lines.push(format!("Total:    {} messages, {} words", messages, words));
+    lines.push(format!(
+        "Average:  {:.1} messages, {:.1} words per day",
+        messages as f64 / days,
+        words as f64 / days
+    ));
+    match daily.iter().filter(|d| d.messages > 0).max_by_key(|d| d.messages) {
+        Some(best) => lines.push(format!(
+            "Best day: {} with {} messages",
+            best.date.format("%Y-%m-%d"),
+            best.messages
+        )),
+        None => lines.push("Best day: none yet".to_owned()),
+    }
+    match rank {
+        Some((rank, ranked)) => lines.push(format!("Rank:     #{} of {} by words", rank, ranked)),
+        None => lines.push("Rank:     unranked".to_owned()),
+    }
+    lines.push(format!("Lifetime: {} messages, {} words", lifetime.messages, lifetime.words));
+    let in_days = |n: i64| if n == 1 { "1 day".to_owned() } else { format!("{} days", n) };
+    lines.push(format!(
+        "Streak:   {} in a row, longest {}",
+        in_days(lifetime.streak), in_days(lifetime.longest_streak)
+    ));
+
+    lines.join("\n")
+}
+
+/// Fills in the days from `since` to `until` that have no statistics.
+fn fill_days(daily: &[DailyStatistics], since: NaiveDate, until: NaiveDate) -> Vec<DailyStatistics> {
+    let mut results = vec![];
+    let mut date = since;
+    while date <= until {
+        results.push(daily.iter().find(|d| d.date == date).cloned().unwrap_or(DailyStatistics {
+            date,
+            messages: 0,
+            words: 0,
+        }));
+        date = date.succ();
+    }
+    results
+}
+
+/// Draws each value as a bar from one eighth to full height of `max`.
+fn sparkline(values: &[i64], max: i64) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    values
+        .iter()
+        .map(|&v| if max > 0 { BARS[(v * 7 / max) as usize] } else { BARS[0] })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32, messages: i64) -> DailyStatistics {
+        DailyStatistics { date: NaiveDate::from_ymd(2026, 10, d), messages, words: messages * 3 }
+    }
+
+    #[test]
+    fn missing_days_are_zero() {
+        let since = NaiveDate::from_ymd(2026, 10, 1);
+        let until = NaiveDate::from_ymd(2026, 10, 4);
+        let filled = fill_days(&[day(2, 5), day(4, 1)], since, until);
+        assert_eq!(filled, vec![day(1, 0), day(2, 5), day(3, 0), day(4, 1)]);
+    }
+
+    #[test]
+    fn leaderboard_options() {
+        let options = StatsOptions::parse("30 by wpm top 5 page 2");
+        assert_eq!(options.days, Some(30));
+        assert_eq!(options.order, Some(StatisticsOrder::WordsPerMessage));
+        assert_eq!(options.count, Some(5));
+        assert_eq!(options.page, Some(2));
+
+        let options = StatsOptions::parse("page 3 messages");
+        assert_eq!(options.days, None);
+        assert_eq!(options.order, Some(StatisticsOrder::Messages));
+    }
+
+    #[test]
+    fn sparkline_scales_to_max() {
+        assert_eq!(sparkline(&[0, 1, 4, 8], 8), "▁▁▄█");
+        assert_eq!(sparkline(&[0, 0], 0), "▁▁");
+    }
+
+    #[test]
+    fn heatmap_is_shifted_into_timezone() {
+        // 2026-10-12 is a monday.
+        let hours = vec![
+            (NaiveDate::from_ymd(2026, 10, 12).and_hms(23, 0, 0), 5),
+            (NaiveDate::from_ymd(2026, 10, 19).and_hms(23, 0, 0), 2),
+        ];
+        assert_eq!(heatmap_grid(&hours, Tz::UTC)[0][23], 7);
+
+        let grid = heatmap_grid(&hours, Tz::Europe__Oslo);
+        assert_eq!(grid[0][23], 0);
+        assert_eq!(grid[1][1], 7);
+    }
+
+    #[test]
+    fn csv_export() {
+        let row = |user_id, messages, words| UserDailyStatistics {
+            user_id: UserId(user_id),
+            date: NaiveDate::from_ymd(2026, 10, 1),
+            messages,
+            words,
+            username: None,
+        };
+        let statistics = vec![row(1, 2, 9), row(2, 1, 3), row(3, 4, 4)];
+        let mut names = HashMap::new();
+        names.insert(UserId(1), "Smith, \"Agent\"".to_owned());
+        names.insert(UserId(3), "=HYPERLINK(\"x\")".to_owned());
+
+        assert_eq!(
+            export_csv(&statistics, &names),
+            "user_id,display_name,date,messages,words\n\
+             1,\"Smith, \"\"Agent\"\"\",2026-10-01,2,9\n\
+             2,,2026-10-01,1,3\n\
+             3,\"'=HYPERLINK(\"\"x\"\")\",2026-10-01,4,4\n"
+        );
+    }
+
+    #[test]
+    fn user_activity_summary() {
+        let lifetime = LifetimeStatistics { messages: 1204, words: 8650, streak: 1, longest_streak: 12 };
+        let daily = [day(1, 2), day(2, 0), day(3, 7)];
+        let summary = describe_user_activity(&daily, (9, 27), 3, Some((3, 10)), &lifetime, None);
+        assert!(summary.contains("Total:    9 messages, 27 words"));
+        assert!(summary.contains("Average:  3.0 messages, 9.0 words per day"));
+        assert!(summary.contains("Best day: 2026-10-03 with 7 messages"));
+        assert!(!summary.contains("monthly"));
+        assert!(summary.contains("Rank:     #3 of 10 by words"));
+        assert!(summary.contains("Lifetime: 1204 messages, 8650 words"));
+        assert!(summary.contains("Streak:   1 day in a row, longest 12 days"));
+    }
+    #[test]
+    fn rolled_up_days_are_not_shown() {
+        let lifetime = LifetimeStatistics { messages: 1204, words: 8650, streak: 0, longest_streak: 12 };
+        let rolled_up = NaiveDate::from_ymd(2026, 10, 3);
+        let summary = describe_user_activity(&[day(3, 7)], (40, 120), 10, None, &lifetime, Some(rolled_up));
+        assert!(summary.starts_with("Only monthly totals are kept from before 2026-10-03"));
+        assert!(summary.contains("Total:    40 messages, 120 words"));
+        assert!(summary.contains("Average:  4.0 messages, 12.0 words per day"));
+        assert!(summary.contains("Best day: 2026-10-03 with 7 messages"));
+    }
+}
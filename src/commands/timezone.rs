@@ -0,0 +1,52 @@
+use chrono::offset::Utc;
+use chrono_tz::{Tz, TZ_VARIANTS};
+use std::str::FromStr;
+
+use util;
+
+static USAGE: &str = "Usage: `!timezone name`, where `name` is a timezone \
+                      like `Europe/Oslo` or `America/New_York`.";
+
+/// Shows or sets the timezone used when reading and printing times for the author.
+command!(timezone(ctx, msg, args) {
+    let mut pool = util::get_pool(ctx);
+    let name = args.full().trim();
+
+    if name.is_empty() {
+        let timezone = pool.get_timezone(msg.author.id)?;
+        util::print_or_log_error(&format!(
+            "Your timezone is {} (currently {}).\n{}",
+            timezone.name(),
+            Utc::now().with_timezone(&timezone).format("%H:%M"),
+            USAGE
+        ), &msg.channel_id);
+        return Ok(());
+    }
+
+    let timezone = match find_timezone(name) {
+        Some(tz) => tz,
+        None => {
+            util::print_or_log_error(&format!("Unknown timezone `{}`.\n{}", name, USAGE), &msg.channel_id);
+            return Ok(());
+        }
+    };
+
+    pool.set_timezone(msg.author.id, timezone)?;
+
+    util::print_or_log_error(&format!(
+        "Timezone set to {}. It's currently {} there.",
+        timezone.name(),
+        Utc::now().with_timezone(&timezone).format("%Y-%m-%d %H:%M %Z")
+    ), &msg.channel_id);
+});
+
+/// Looks up an IANA timezone name, ignoring case.
+pub fn find_timezone(name: &str) -> Option<Tz> {
+    Tz::from_str(name).ok().or_else(|| {
+        let name = name.to_lowercase();
+        TZ_VARIANTS
+            .iter()
+            .find(|tz| tz.name().to_lowercase() == name)
+            .cloned()
+    })
+}
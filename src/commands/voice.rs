@@ -0,0 +1,156 @@
+use chrono::offset::Utc;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use serenity::model::guild::Guild;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::voice::VoiceState;
+use std::cmp;
+use std::thread;
+use std::time;
+
+use super::statistics::MAX_DAYS;
+use command_error::CommandError;
+use connectionpool::ConnectionPool;
+use util;
+
+/// How often the voice sessions going on are noted as still going on, in seconds.
+const HEARTBEAT_INTERVAL_SECS: u64 = 60;
+
+/// Starts or ends the voice session of a user when they join, leave or move
+/// between voice channels, or go AFK or deafen themselves.
+pub fn update_voice_state(ctx: &Context, guild_id: Option<GuildId>, state: &VoiceState) {
+    let guild_id = match guild_id {
+        Some(id) => id,
+        None => return,
+    };
+    if state.user_id.find().map_or(false, |u| u.read().bot) {
+        return;
+    }
+
+    let afk_channel_id = guild_id.find().and_then(|g| g.read().afk_channel_id);
+    let channel_id = counted_channel(state.channel_id, state.self_deaf, afk_channel_id);
+    let now = Utc::now().naive_utc();
+    if let Err(why) = util::get_pool(ctx).track_voice_session(guild_id, state.user_id, channel_id, now) {
+        error!("Failed to track voice activity: {}", why);
+    }
+}
+
+/// Brings the voice sessions of a guild in line with who is in voice now,
+/// for when the bot starts or reconnects. Nobody knows who left while the bot
+/// was away, or when, so the sessions going on end when the bot last saw them
+/// and those in voice now start new ones.
+pub fn resume_voice_sessions(ctx: &Context, guild: &Guild) {
+    let mut pool = util::get_pool(ctx);
+    let now = Utc::now().naive_utc();
+
+    if let Err(why) = pool.end_guild_voice_sessions(guild.id) {
+        error!("Failed to end voice sessions: {}", why);
+        return;
+    }
+
+    for (user_id, state) in &guild.voice_states {
+        if guild.members.get(user_id).map_or(false, |m| m.user.read().bot) {
+            continue;
+        }
+        let channel_id = counted_channel(state.channel_id, state.self_deaf, guild.afk_channel_id);
+        if let Err(why) = pool.track_voice_session(guild.id, *user_id, channel_id, now) {
+            error!("Failed to track voice activity: {}", why);
+        }
+    }
+}
+
+/// Infinite loop that notes every `HEARTBEAT_INTERVAL_SECS` seconds that the
+/// voice sessions going on still are, so that a crash costs at most that long.
+pub fn heartbeat_periodically(mut pool: ConnectionPool) -> ! {
+    loop {
+        thread::sleep(time::Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
+        if let Err(why) = pool.touch_voice_sessions(Utc::now().naive_utc()) {
+            error!("Failed to update voice sessions: {}", why);
+        }
+    }
+}
+
+/// Lists the ten users who spent the most time in voice the last `x` days.
+pub fn stats(ctx: &Context, msg: &Message, text: &str) -> Result<(), CommandError> {
+    let guild_id = match msg.guild_id() {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+    let mut pool = util::get_pool(ctx);
+
+    let days = text
+        .split_whitespace()
+        .filter_map(|d| d.parse().ok())
+        .next()
+        .unwrap_or(util::DEFAULT_DAYS);
+    let days = cmp::min(cmp::max(days, 1), MAX_DAYS);
+    let since = util::first_day(&mut pool, msg.author.id, days)?;
+    let statistics = pool.get_voice_statistics(guild_id, since, Utc::now().naive_utc())?;
+    if statistics.is_empty() {
+        util::print_or_log_error(
+            &format!("Nobody has been in voice the last {} days.", days),
+            &msg.channel_id,
+        );
+        return Ok(());
+    }
+
+    let names: Vec<String> = statistics
+        .iter()
+        .map(|s| util::member_name(guild_id, s.user_id, s.username.as_ref()))
+        .collect();
+    let times: Vec<String> = statistics.iter().map(|s| format_duration(s.seconds)).collect();
+    let name_width = names.iter().map(|n| n.chars().count()).max().unwrap_or(5);
+    let time_width = times.iter().map(|t| t.len()).max().unwrap_or(5);
+
+    let result = names
+        .iter()
+        .zip(times.iter())
+        .map(|(name, time)| format!("{:<nw$} | {:>tw$}", name, time, nw = name_width, tw = time_width))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    util::print_or_log_error(&format!(
+        "Most time in voice the last {} days (since {}), not counting AFK or deafened:\
+         \n```\n{}\n```",
+        days, since.format("%Y-%m-%d"), result
+    ), &msg.channel_id);
+    Ok(())
+}
+
+/// The channel a voice state counts towards, if any. Time in the AFK channel
+/// or while deafened by oneself doesn't count.
+fn counted_channel(
+    channel_id: Option<ChannelId>,
+    self_deaf: bool,
+    afk_channel_id: Option<ChannelId>,
+) -> Option<ChannelId> {
+    match channel_id {
+        Some(id) if !self_deaf && Some(id) != afk_channel_id => Some(id),
+        _ => None,
+    }
+}
+
+/// Writes a number of seconds as hours and minutes, like `12h 05m`.
+fn format_duration(seconds: i64) -> String {
+    format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn afk_and_deafened_time_is_not_counted() {
+        let afk = Some(ChannelId(2));
+        assert_eq!(counted_channel(Some(ChannelId(1)), false, afk), Some(ChannelId(1)));
+        assert_eq!(counted_channel(Some(ChannelId(1)), true, afk), None);
+        assert_eq!(counted_channel(Some(ChannelId(2)), false, afk), None);
+        assert_eq!(counted_channel(None, false, afk), None);
+    }
+
+    #[test]
+    fn durations_are_hours_and_minutes() {
+        assert_eq!(format_duration(0), "0h 00m");
+        assert_eq!(format_duration(3 * 3600 + 5 * 60 + 59), "3h 05m");
+    }
+}
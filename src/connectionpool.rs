@@ -1,13 +1,66 @@
 use chrono::{NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
 use env;
+use postgres::rows::Row;
 use r2d2::{Pool, PooledConnection};
 use r2d2_postgres::{PostgresConnectionManager, TlsMode};
-use serenity::model::guild;
-use serenity::model::id::{GuildId, UserId};
+use serenity::model::id::{ChannelId, EmojiId, GuildId, MessageId, RoleId, UserId};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 use typemap::Key;
 
 use command_error::CommandError;
+use migrations;
+use timeparse::Recurrence;
+
+/// Columns read by `read_reminder`, for reminders `r` joined with timezones `t`.
+const REMINDER_COLUMNS: &str = "r.id, r.user_id, r.message, r.bookmark, r.guild_id, r.date, \
+                                t.timezone, r.recurrence, r.channel_id, r.in_channel, r.attempts, \
+                                r.message_id";
+
+/// Statistics per guild, channel, user and day since the date `$2`, from both
+/// the daily statistics and the months that have been rolled up. Rolled up
+/// months are spread evenly over their days, so a period can start within one.
+/// Those days are made up, so this is only for totals over a period.
+const STATISTICS_SINCE: &str = "(SELECT guild_id, channel_id, user_id, date, messages, words
+                                  FROM statistics WHERE date >= $2
+                                  UNION ALL
+                                  SELECT guild_id, channel_id, user_id, date,
+                                         (messages::BIGINT * (i + 1) / days
+                                          - messages::BIGINT * i / days)::INTEGER,
+                                         (words::BIGINT * (i + 1) / days
+                                          - words::BIGINT * i / days)::INTEGER
+                                  FROM (SELECT m.*, day::DATE AS date, day::DATE - month AS i,
+                                               (month + INTERVAL '1 month')::DATE - month AS days
+                                        FROM monthly_statistics m, generate_series(
+                                            month, month + INTERVAL '1 month' - INTERVAL '1 day',
+                                            INTERVAL '1 day'
+                                        ) AS day
+                                        WHERE month > $2 - INTERVAL '1 month') spread
+                                  WHERE date >= $2) AS combined";
+
+/// Ends the voice sessions going on that match `condition` at `ended_at`, and
+/// adds their time to the voice statistics of each day.
+fn end_voice_sessions_sql(condition: &str, ended_at: &str) -> String {
+    format!(
+        "WITH ended AS (
+            UPDATE voice_sessions SET ended_at = GREATEST({}, started_at)
+            WHERE {} AND ended_at IS NULL
+            RETURNING guild_id, user_id, started_at, ended_at
+        )
+        INSERT INTO voice_statistics (guild_id, user_id, date, seconds)
+        SELECT guild_id, user_id, CAST(day AS DATE),
+               SUM(CAST(EXTRACT(EPOCH FROM LEAST(ended_at, day + INTERVAL '1 day')
+                                          - GREATEST(started_at, day)) AS INTEGER))
+        FROM ended,
+             generate_series(date_trunc('day', started_at), ended_at, INTERVAL '1 day') AS day
+        WHERE day < ended_at
+        GROUP BY 1, 2, 3
+        ON CONFLICT(guild_id, user_id, date) DO UPDATE SET
+            seconds = voice_statistics.seconds + EXCLUDED.seconds",
+        ended_at, condition
+    )
+}
 
 #[derive(Clone)]
 pub struct ConnectionPool {
@@ -29,49 +82,372 @@ impl ConnectionPool {
         self.pool.get().expect("Failed to get postgres connection.")
     }
 
-    pub fn update_statistics(
+    /// Brings the database schema up to date. See `migrations`.
+    pub fn migrate(&mut self) -> Result<(), CommandError> {
+        migrations::run(&self.get_conn())
+    }
+
+    /// Adds message and word counts per guild, channel, user and hour, both to
+    /// the daily statistics and to the hourly statistics of the guild, along
+    /// with the running totals and streaks of days members posted on, whether
+    /// new members posted, usernames, the use of emoji and XP. Returns how the
+    /// XP changed.
+    pub fn update_statistics(&mut self, pending: &PendingStatistics) -> Result<Vec<XpChange>, CommandError> {
+        // Each row may only be updated once per statement, so sum up the
+        // counts per day and per guild and hour first.
+        let mut daily = HashMap::new();
+        let mut hourly = HashMap::new();
+        for (key, delta) in &pending.counts {
+            let day = (key.guild_id, key.channel_id, key.user_id, key.hour.date());
+            daily.entry(day).or_insert_with(StatisticsDelta::default).add(delta);
+            hourly.entry((key.guild_id, key.hour)).or_insert_with(StatisticsDelta::default).add(delta);
+        }
+
+        let mut guild_ids = vec![];
+        let mut channel_ids = vec![];
+        let mut user_ids = vec![];
+        let mut dates = vec![];
+        let mut messages = vec![];
+        let mut words = vec![];
+        for (&(guild_id, channel_id, user_id, date), delta) in &daily {
+            guild_ids.push(format!("{}", guild_id.0));
+            channel_ids.push(format!("{}", channel_id.0));
+            user_ids.push(format!("{}", user_id.0));
+            dates.push(date);
+            messages.push(delta.messages);
+            words.push(delta.words);
+        }
+
+        let conn = self.get_conn();
+        let transaction = conn.transaction()?;
+        transaction.execute(
+            "INSERT INTO statistics (guild_id, channel_id, user_id, date, messages, words)
+            SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::DATE[],
+                                 $5::INTEGER[], $6::INTEGER[])
+            ON CONFLICT(guild_id, channel_id, user_id, date) DO UPDATE SET
+                messages = statistics.messages + EXCLUDED.messages,
+                words = statistics.words + EXCLUDED.words",
+            &[&guild_ids, &channel_ids, &user_ids, &dates, &messages, &words],
+        )?;
+
+        let mut totals: HashMap<(GuildId, UserId), (i64, i64)> = HashMap::new();
+        for (&(guild_id, _, user_id, _), delta) in &daily {
+            let total = totals.entry((guild_id, user_id)).or_insert((0, 0));
+            total.0 += i64::from(delta.messages);
+            total.1 += i64::from(delta.words);
+        }
+        let mut guild_ids = vec![];
+        let mut user_ids = vec![];
+        let mut messages = vec![];
+        let mut words = vec![];
+        for (&(guild_id, user_id), &(m, w)) in &totals {
+            guild_ids.push(format!("{}", guild_id.0));
+            user_ids.push(format!("{}", user_id.0));
+            messages.push(m);
+            words.push(w);
+        }
+
+        transaction.execute(
+            "INSERT INTO member_totals (guild_id, user_id, messages, words)
+            SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::BIGINT[], $4::BIGINT[])
+            ON CONFLICT(guild_id, user_id) DO UPDATE SET
+                messages = member_totals.messages + EXCLUDED.messages,
+                words = member_totals.words + EXCLUDED.words",
+            &[&guild_ids, &user_ids, &messages, &words],
+        )?;
+
+        // Members who joined and hadn't posted since have now.
+        let mut guild_ids = vec![];
+        let mut user_ids = vec![];
+        let mut dates = vec![];
+        for &(guild_id, _, user_id, date) in daily.keys() {
+            guild_ids.push(format!("{}", guild_id.0));
+            user_ids.push(format!("{}", user_id.0));
+            dates.push(date);
+        }
+        transaction.execute(
+            "UPDATE member_events e SET first_posted = posted.date
+            FROM (SELECT guild_id, user_id, MIN(date) AS date
+                  FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::DATE[]) AS p(guild_id, user_id, date)
+                  GROUP BY guild_id, user_id) posted
+            WHERE e.guild_id = posted.guild_id AND e.user_id = posted.user_id
+              AND e.joined AND e.first_posted IS NULL AND posted.date >= CAST(e.at AS DATE)",
+            &[&guild_ids, &user_ids, &dates],
+        )?;
+
+        // A streak goes on if the member was last active the day before, so
+        // days are added one at a time, in order.
+        let mut active: BTreeMap<NaiveDate, HashSet<(GuildId, UserId)>> = BTreeMap::new();
+        for &(guild_id, _, user_id, date) in daily.keys() {
+            active.entry(date).or_insert_with(HashSet::new).insert((guild_id, user_id));
+        }
+        for (date, members) in &active {
+            let guild_ids: Vec<String> = members.iter().map(|&(g, _)| format!("{}", g.0)).collect();
+            let user_ids: Vec<String> = members.iter().map(|&(_, u)| format!("{}", u.0)).collect();
+            transaction.execute(
+                "INSERT INTO member_streaks (guild_id, user_id, started, last_active, longest)
+                SELECT guild_id, user_id, $3, $3, 1
+                FROM UNNEST($1::VARCHAR[], $2::VARCHAR[]) AS active(guild_id, user_id)
+                ON CONFLICT(guild_id, user_id) DO UPDATE SET
+                    started = CASE WHEN member_streaks.last_active >= $3 - 1
+                                   THEN member_streaks.started ELSE $3 END,
+                    last_active = GREATEST(member_streaks.last_active, $3),
+                    longest = GREATEST(member_streaks.longest,
+                                       GREATEST(member_streaks.last_active, $3)
+                                       - CASE WHEN member_streaks.last_active >= $3 - 1
+                                              THEN member_streaks.started ELSE $3 END + 1)",
+                &[&guild_ids, &user_ids, date],
+            )?;
+        }
+
+        let mut guild_ids = vec![];
+        let mut hours = vec![];
+        let mut messages = vec![];
+        let mut words = vec![];
+        for (&(guild_id, hour), delta) in &hourly {
+            guild_ids.push(format!("{}", guild_id.0));
+            hours.push(hour);
+            messages.push(delta.messages);
+            words.push(delta.words);
+        }
+
+        transaction.execute(
+            "INSERT INTO hourly_statistics (guild_id, hour, messages, words)
+            SELECT * FROM UNNEST($1::VARCHAR[], $2::TIMESTAMP[], $3::INTEGER[], $4::INTEGER[])
+            ON CONFLICT(guild_id, hour) DO UPDATE SET
+                messages = hourly_statistics.messages + EXCLUDED.messages,
+                words = hourly_statistics.words + EXCLUDED.words",
+            &[&guild_ids, &hours, &messages, &words],
+        )?;
+
+        let user_ids: Vec<String> = pending.usernames.keys().map(|id| format!("{}", id.0)).collect();
+        let names: Vec<&str> = pending.usernames.values().map(|n| n.as_str()).collect();
+        transaction.execute(
+            "INSERT INTO usernames (user_id, username)
+            SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[])
+            ON CONFLICT(user_id) DO UPDATE SET
+                username = EXCLUDED.username,
+                updated_at = current_timestamp",
+            &[&user_ids, &names],
+        )?;
+
+        let mut guild_ids = vec![];
+        let mut emoji_ids = vec![];
+        let mut dates = vec![];
+        let mut messages = vec![];
+        let mut reactions = vec![];
+        for (key, delta) in &pending.emoji {
+            guild_ids.push(format!("{}", key.guild_id.0));
+            emoji_ids.push(format!("{}", key.emoji_id.0));
+            dates.push(key.date);
+            messages.push(delta.messages);
+            reactions.push(delta.reactions);
+        }
+
+        transaction.execute(
+            "INSERT INTO emoji_statistics (guild_id, emoji_id, date, messages, reactions)
+            SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::DATE[], $4::INTEGER[], $5::INTEGER[])
+            ON CONFLICT(guild_id, emoji_id, date) DO UPDATE SET
+                messages = emoji_statistics.messages + EXCLUDED.messages,
+                reactions = emoji_statistics.reactions + EXCLUDED.reactions",
+            &[&guild_ids, &emoji_ids, &dates, &messages, &reactions],
+        )?;
+
+        let mut guild_ids = vec![];
+        let mut user_ids = vec![];
+        let mut xp = vec![];
+        for (&(guild_id, user_id), &earned) in &pending.xp {
+            guild_ids.push(format!("{}", guild_id.0));
+            user_ids.push(format!("{}", user_id.0));
+            xp.push(earned);
+        }
+
+        let rows = transaction.query(
+            "INSERT INTO member_xp (guild_id, user_id, xp)
+            SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::BIGINT[])
+            ON CONFLICT(guild_id, user_id) DO UPDATE SET xp = member_xp.xp + EXCLUDED.xp
+            RETURNING guild_id, user_id, xp",
+            &[&guild_ids, &user_ids, &xp],
+        )?;
+        let mut changes = vec![];
+        for row in rows.iter() {
+            let guild_id: String = row.get(0);
+            let user_id: String = row.get(1);
+            let new_xp: i64 = row.get(2);
+            match (u64::from_str(&guild_id), u64::from_str(&user_id)) {
+                (Ok(guild_id), Ok(user_id)) => {
+                    let key = (GuildId(guild_id), UserId(user_id));
+                    changes.push(XpChange {
+                        guild_id: key.0,
+                        user_id: key.1,
+                        old_xp: new_xp - pending.xp.get(&key).cloned().unwrap_or(0),
+                        new_xp,
+                    });
+                }
+                _ => error!("Failed to parse ids {} and {} to int.", guild_id, user_id),
+            }
+        }
+        transaction.commit()?;
+
+        Ok(changes)
+    }
+
+    /// Moves the daily statistics from before `before` into monthly statistics
+    /// and returns how many monthly rows were written.
+    pub fn roll_up_statistics(&mut self, before: NaiveDate) -> Result<u64, CommandError> {
+        let written = self.get_conn().execute(
+            "WITH moved AS (DELETE FROM statistics WHERE date < $1 RETURNING *)
+            INSERT INTO monthly_statistics (guild_id, channel_id, user_id, month, messages, words)
+            SELECT guild_id, channel_id, user_id, CAST(date_trunc('month', date) AS DATE),
+                   SUM(messages), SUM(words)
+            FROM moved
+            GROUP BY 1, 2, 3, 4
+            ON CONFLICT(guild_id, channel_id, user_id, month) DO UPDATE SET
+                messages = monthly_statistics.messages + EXCLUDED.messages,
+                words = monthly_statistics.words + EXCLUDED.words",
+            &[&before],
+        )?;
+
+        Ok(written)
+    }
+
+    /// Deletes the hourly statistics from before `before`.
+    pub fn prune_hourly_statistics(&mut self, before: NaiveDateTime) -> Result<u64, CommandError> {
+        let deleted = self.get_conn().execute(
+            "DELETE FROM hourly_statistics WHERE hour < $1",
+            &[&before],
+        )?;
+
+        Ok(deleted)
+    }
+
+    /// Gets how often each custom emoji was used in a guild since `since`.
+    /// Emoji that weren't used are left out.
+    pub fn get_emoji_statistics(
+        &mut self,
+        guild_id: GuildId,
+        since: NaiveDate,
+    ) -> Result<Vec<EmojiStatistics>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT emoji_id, SUM(messages), SUM(reactions) FROM emoji_statistics
+             WHERE guild_id = $1 AND date >= $2
+             GROUP BY emoji_id",
+            &[&format!("{}", guild_id.0), &since],
+        )?;
+
+        let mut results = vec![];
+        for row in rows.iter() {
+            let emoji_id: String = row.get(0);
+            match u64::from_str(&emoji_id) {
+                Ok(id) => results.push(EmojiStatistics {
+                    emoji_id: EmojiId(id),
+                    messages: row.get(1),
+                    reactions: row.get(2),
+                }),
+                Err(_) => error!("Failed to parse id {} to int.", emoji_id),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Records a member joining or leaving a guild.
+    pub fn add_member_event(
         &mut self,
         guild_id: GuildId,
         user_id: UserId,
-        date: NaiveDate,
-        messages: i32,
-        words: i32,
+        joined: bool,
+        at: NaiveDateTime,
     ) -> Result<(), CommandError> {
         self.get_conn().execute(
-            "INSERT INTO statistics (guild_id, user_id, date, messages, words)
-            VALUES ($1, $2, $3, $4, $5)
-            ON CONFLICT(guild_id, user_id, date) DO UPDATE SET
-                messages = statistics.messages + $4,
-                words = statistics.words + $5",
-            &[
-                &format!("{}", guild_id.0),
-                &format!("{}", user_id.0),
-                &date,
-                &messages,
-                &words,
-            ],
+            "INSERT INTO member_events (guild_id, user_id, joined, at) VALUES ($1, $2, $3, $4)",
+            &[&format!("{}", guild_id.0), &format!("{}", user_id.0), &joined, &at],
         )?;
 
         Ok(())
     }
 
+    /// Gets how many members joined and left a guild since `since`, and how
+    /// many of those who joined posted within their first week.
+    pub fn get_member_statistics(
+        &mut self,
+        guild_id: GuildId,
+        since: NaiveDate,
+    ) -> Result<MemberStatistics, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT COUNT(*) FILTER (WHERE joined),
+                    COUNT(*) FILTER (WHERE NOT joined),
+                    COUNT(DISTINCT user_id) FILTER (WHERE joined),
+                    COUNT(DISTINCT user_id) FILTER (
+                        WHERE joined AND first_posted < CAST(at AS DATE) + 7
+                    )
+             FROM member_events
+             WHERE guild_id = $1 AND at >= $2::DATE",
+            &[&format!("{}", guild_id.0), &since],
+        )?;
+
+        let row = rows.get(0);
+        Ok(MemberStatistics {
+            joins: row.get(0),
+            leaves: row.get(1),
+            new_members: row.get(2),
+            posted_first_week: row.get(3),
+        })
+    }
+
+    /// Gets the messages per UTC hour in a guild since `since`.
+    /// Hours without messages are left out.
+    pub fn get_hourly_statistics(
+        &mut self,
+        guild_id: GuildId,
+        since: NaiveDateTime,
+    ) -> Result<Vec<(NaiveDateTime, i64)>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT hour, CAST(messages AS BIGINT) FROM hourly_statistics
+             WHERE guild_id = $1 AND hour >= $2",
+            &[&format!("{}", guild_id.0), &since],
+        )?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    /// Gets a page of the most active users since `since`, in one channel or
+    /// all of them, and how many users there are in total.
     pub fn get_statistics(
         &mut self,
         guild_id: GuildId,
-        days: u32,
-    ) -> Result<Vec<Statistics>, CommandError> {
+        since: NaiveDate,
+        channel_id: Option<ChannelId>,
+        order: StatisticsOrder,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Statistics>, i64), CommandError> {
         // Don't reply to PM's as the command is only valid for guilds.
         let rows = self.get_conn().query(
-            "SELECT user_id, SUM(messages) as messages, SUM(words) as words FROM statistics
-             WHERE guild_id = $1 AND date > current_date - CAST ($2 AS INTEGER)
-             GROUP BY user_id
-             ORDER BY words DESC
-             fetch first 10 rows only",
-            &[&format!("{}", guild_id.0), &days],
+            &format!(
+                "SELECT user_id, SUM(messages) as messages, SUM(words) as words,
+                        COUNT(*) OVER () AS total, MIN(username)
+                 FROM {} LEFT JOIN usernames USING (user_id)
+                 WHERE guild_id = $1 AND ($3::VARCHAR IS NULL OR channel_id = $3)
+                 GROUP BY user_id
+                 ORDER BY {} DESC, user_id
+                 LIMIT $4 OFFSET $5",
+                STATISTICS_SINCE,
+                order.sql()
+            ),
+            &[
+                &format!("{}", guild_id.0),
+                &since,
+                &channel_id.map(|c| format!("{}", c.0)),
+                &limit,
+                &offset,
+            ],
         )?;
 
         let mut results = vec![];
+        let mut total = 0;
         for row in rows.into_iter() {
+            total = row.get(3);
             let user_id: String = row.get(0);
             let user_id = match u64::from_str(&user_id) {
                 Ok(id) => id,
@@ -81,92 +457,1273 @@ impl ConnectionPool {
                 }
             };
 
-            let user = match guild_id.member(user_id) {
-                Ok(user) => user,
-                Err(_) => {
-                    error!("Failed to look up member from id {}.", user_id);
-                    continue;
-                }
-            };
-
             let messages: i64 = row.get(1);
             let chars: i64 = row.get(2);
             results.push(Statistics {
-                user_id: user,
+                user_id: UserId(user_id),
+                username: row.get(4),
                 messages: messages,
                 words: chars,
             });
         }
-        results.sort_by(|a, b| b.words.cmp(&a.words));
+
+        Ok((results, total))
+    }
+
+    /// Gets the ten most active channels since `since`, by messages.
+    pub fn get_channel_statistics(
+        &mut self,
+        guild_id: GuildId,
+        since: NaiveDate,
+    ) -> Result<Vec<ChannelStatistics>, CommandError> {
+        let rows = self.get_conn().query(
+            &format!(
+                "SELECT channel_id, SUM(messages) as messages, SUM(words) as words FROM {}
+                 WHERE guild_id = $1 AND channel_id <> ''
+                 GROUP BY channel_id
+                 ORDER BY messages DESC
+                 fetch first 10 rows only",
+                STATISTICS_SINCE
+            ),
+            &[&format!("{}", guild_id.0), &since],
+        )?;
+
+        let mut results = vec![];
+        for row in rows.iter() {
+            let channel_id: String = row.get(0);
+            match u64::from_str(&channel_id) {
+                Ok(id) => results.push(ChannelStatistics {
+                    channel_id: ChannelId(id),
+                    messages: row.get(1),
+                    words: row.get(2),
+                }),
+                Err(_) => error!("Failed to parse id {} to int.", channel_id),
+            }
+        }
 
         Ok(results)
     }
 
-    pub fn add_reminder(
+    /// Gets the messages and words of every user per day since `since`,
+    /// ordered by date and user. Rolled up months aren't known per day and are
+    /// left out.
+    pub fn get_users_daily_statistics(
         &mut self,
-        user_id: &UserId,
-        guild_id: &Option<GuildId>,
-        date: NaiveDateTime,
-        message: &str,
-        bookmark: &str,
+        guild_id: GuildId,
+        since: NaiveDate,
+    ) -> Result<Vec<UserDailyStatistics>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT user_id, date, messages, words, username FROM (
+                SELECT user_id, date, SUM(messages) as messages, SUM(words) as words
+                FROM statistics
+                WHERE guild_id = $1 AND date >= $2
+                GROUP BY user_id, date
+             ) daily LEFT JOIN usernames USING (user_id)
+             ORDER BY date, user_id",
+            &[&format!("{}", guild_id.0), &since],
+        )?;
+
+        let mut results = vec![];
+        for row in rows.iter() {
+            let user_id: String = row.get(0);
+            match u64::from_str(&user_id) {
+                Ok(id) => results.push(UserDailyStatistics {
+                    user_id: UserId(id),
+                    date: row.get(1),
+                    messages: row.get(2),
+                    words: row.get(3),
+                    username: row.get(4),
+                }),
+                Err(_) => error!("Failed to parse id {} to int.", user_id),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Gets the messages and words of the whole guild per day since `since`.
+    /// Days without messages and rolled up months are left out.
+    pub fn get_guild_daily_statistics(
+        &mut self,
+        guild_id: GuildId,
+        since: NaiveDate,
+    ) -> Result<Vec<DailyStatistics>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT date, SUM(messages) as messages, SUM(words) as words FROM statistics
+             WHERE guild_id = $1 AND date >= $2
+             GROUP BY date
+             ORDER BY date",
+            &[&format!("{}", guild_id.0), &since],
+        )?;
+
+        Ok(rows.iter().map(|row| read_daily_statistics(&row)).collect())
+    }
+
+    /// Gets the messages and words of one user per day since `since`.
+    /// Days without messages and rolled up months are left out.
+    pub fn get_user_daily_statistics(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        since: NaiveDate,
+    ) -> Result<Vec<DailyStatistics>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT date, SUM(messages) as messages, SUM(words) as words FROM statistics
+             WHERE guild_id = $1 AND user_id = $2 AND date >= $3
+             GROUP BY date
+             ORDER BY date",
+            &[&format!("{}", guild_id.0), &format!("{}", user_id.0), &since],
+        )?;
+
+        Ok(rows.iter().map(|row| read_daily_statistics(&row)).collect())
+    }
+
+    /// Gets the messages and words of one user since `since`, including the
+    /// part of rolled up months in the period.
+    pub fn get_user_totals(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        since: NaiveDate,
+    ) -> Result<(i64, i64), CommandError> {
+        let rows = self.get_conn().query(
+            &format!(
+                "SELECT COALESCE(SUM(messages), 0), COALESCE(SUM(words), 0) FROM {}
+                 WHERE guild_id = $1 AND user_id = $3",
+                STATISTICS_SINCE
+            ),
+            &[&format!("{}", guild_id.0), &since, &format!("{}", user_id.0)],
+        )?;
+
+        Ok(rows.iter().next().map_or((0, 0), |row| (row.get(0), row.get(1))))
+    }
+
+    /// Gets the first day after the months of a guild that have been rolled
+    /// up, before which statistics are only known per month.
+    pub fn get_rolled_up_until(&mut self, guild_id: GuildId) -> Result<Option<NaiveDate>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT CAST(MAX(month) + INTERVAL '1 month' AS DATE) FROM monthly_statistics
+             WHERE guild_id = $1",
+            &[&format!("{}", guild_id.0)],
+        )?;
+
+        Ok(rows.iter().next().and_then(|row| row.get(0)))
+    }
+
+    /// Gets the rank of a user by words since `since`, and how many users were
+    /// ranked, or `None` if they haven't said anything.
+    pub fn get_user_rank(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        since: NaiveDate,
+    ) -> Result<Option<(i64, i64)>, CommandError> {
+        let rows = self.get_conn().query(
+            &format!(
+                "SELECT rank, ranked FROM (
+                    SELECT user_id, RANK() OVER (ORDER BY SUM(words) DESC) AS rank,
+                           COUNT(*) OVER () AS ranked
+                    FROM {}
+                    WHERE guild_id = $1
+                    GROUP BY user_id
+                 ) ranks WHERE user_id = $3",
+                STATISTICS_SINCE
+            ),
+            &[&format!("{}", guild_id.0), &since, &format!("{}", user_id.0)],
+        )?;
+
+        Ok(rows.iter().next().map(|row| (row.get(0), row.get(1))))
+    }
+
+    /// Gets the last known username of a user.
+    pub fn get_username(&mut self, user_id: UserId) -> Result<Option<String>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT username FROM usernames WHERE user_id = $1",
+            &[&format!("{}", user_id.0)],
+        )?;
+
+        Ok(rows.iter().next().map(|row| row.get(0)))
+    }
+
+    /// Ends the voice session of a user unless it's in `channel_id`, and starts
+    /// one there if they don't have one yet. Events of the same user may be
+    /// handled at the same time, so this is done under a lock per user.
+    pub fn track_voice_session(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        channel_id: Option<ChannelId>,
+        at: NaiveDateTime,
     ) -> Result<(), CommandError> {
-        let server_id = guild_id.and_then(|g| Some(format!("{}", g)));
+        let guild_id = format!("{}", guild_id.0);
+        let user_id = format!("{}", user_id.0);
+        let channel_id = channel_id.map(|c| format!("{}", c.0));
+
+        let conn = self.get_conn();
+        let transaction = conn.transaction()?;
+        transaction.execute(
+            "SELECT pg_advisory_xact_lock(hashtext('voice:' || $1 || ':' || $2))",
+            &[&guild_id, &user_id],
+        )?;
+        transaction.execute(
+            &end_voice_sessions_sql(
+                "guild_id = $1 AND user_id = $2 AND channel_id IS DISTINCT FROM $3",
+                "$4::TIMESTAMP",
+            ),
+            &[&guild_id, &user_id, &channel_id, &at],
+        )?;
+        if let Some(channel_id) = channel_id {
+            transaction.execute(
+                "INSERT INTO voice_sessions (guild_id, channel_id, user_id, started_at, last_seen_at)
+                VALUES ($1, $2, $3, $4, $4)
+                ON CONFLICT (guild_id, user_id) WHERE ended_at IS NULL DO NOTHING",
+                &[&guild_id, &channel_id, &user_id, &at],
+            )?;
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+
+    /// Notes that the voice sessions going on were still going on at `at`.
+    pub fn touch_voice_sessions(&mut self, at: NaiveDateTime) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "UPDATE voice_sessions SET last_seen_at = $1 WHERE ended_at IS NULL",
+            &[&at],
+        )?;
+
+        Ok(())
+    }
+
+    /// Ends the voice sessions going on in a guild when the bot last saw them,
+    /// and adds their time to the voice statistics of each day.
+    pub fn end_guild_voice_sessions(&mut self, guild_id: GuildId) -> Result<(), CommandError> {
         self.get_conn().execute(
-            "INSERT INTO reminders (user_id, date, message, bookmark, guild_id) VALUES ($1, $2, $3, $4)",
-            &[&format!("{}", user_id.0), &date, &message, &bookmark, &server_id],
+            &end_voice_sessions_sql("guild_id = $1", "last_seen_at"),
+            &[&format!("{}", guild_id.0)],
         )?;
 
         Ok(())
     }
 
-    pub fn get_expired_reminders(&mut self) -> Result<Vec<Reminder>, CommandError> {
+    /// Ends every voice session going on at `at`, and adds their time to the
+    /// voice statistics of each day.
+    pub fn end_voice_sessions(&mut self, at: NaiveDateTime) -> Result<(), CommandError> {
+        self.get_conn().execute(&end_voice_sessions_sql("TRUE", "$1::TIMESTAMP"), &[&at])?;
+
+        Ok(())
+    }
+
+    /// Gets the ten users who spent the most time in voice since `since`,
+    /// counting sessions going on until `now`.
+    pub fn get_voice_statistics(
+        &mut self,
+        guild_id: GuildId,
+        since: NaiveDate,
+        now: NaiveDateTime,
+    ) -> Result<Vec<VoiceStatistics>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT user_id, CAST(SUM(seconds) AS BIGINT), MIN(username) FROM (
+                SELECT user_id, CAST(seconds AS BIGINT) AS seconds FROM voice_statistics
+                WHERE guild_id = $1 AND date >= $2
+                UNION ALL
+                SELECT user_id, CAST(EXTRACT(EPOCH FROM $3 - GREATEST(started_at, $2)) AS BIGINT)
+                FROM voice_sessions
+                WHERE guild_id = $1 AND ended_at IS NULL
+             ) AS voice LEFT JOIN usernames USING (user_id)
+             GROUP BY user_id
+             ORDER BY 2 DESC, user_id
+             fetch first 10 rows only",
+            &[&format!("{}", guild_id.0), &since, &now],
+        )?;
+
+        let mut results = vec![];
+        for row in rows.iter() {
+            let user_id: String = row.get(0);
+            match u64::from_str(&user_id) {
+                Ok(id) => results.push(VoiceStatistics {
+                    user_id: UserId(id),
+                    username: row.get(2),
+                    seconds: row.get(1),
+                }),
+                Err(_) => error!("Failed to parse id {} to int.", user_id),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Deletes the voice sessions that ended before `before`. Their time is
+    /// kept in the voice statistics.
+    pub fn prune_voice_sessions(&mut self, before: NaiveDateTime) -> Result<u64, CommandError> {
+        let deleted = self.get_conn().execute(
+            "DELETE FROM voice_sessions WHERE ended_at < $1",
+            &[&before],
+        )?;
+
+        Ok(deleted)
+    }
+
+    /// Gets the XP of a member, their rank by XP and how many were ranked, or
+    /// `None` if they haven't earned any.
+    pub fn get_xp_rank(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Option<(i64, i64, i64)>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT xp, rank, ranked FROM (
+                SELECT user_id, xp, RANK() OVER (ORDER BY xp DESC) AS rank,
+                       COUNT(*) OVER () AS ranked
+                FROM member_xp WHERE guild_id = $1
+             ) ranks WHERE user_id = $2",
+            &[&format!("{}", guild_id.0), &format!("{}", user_id.0)],
+        )?;
+
+        Ok(rows.iter().next().map(|row| (row.get(0), row.get(1), row.get(2))))
+    }
+
+    /// Gets a page of the members with the most XP.
+    pub fn get_xp_leaderboard(
+        &mut self,
+        guild_id: GuildId,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<XpStatistics>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT user_id, xp, username FROM member_xp LEFT JOIN usernames USING (user_id)
+             WHERE guild_id = $1
+             ORDER BY xp DESC, user_id
+             LIMIT $2 OFFSET $3",
+            &[&format!("{}", guild_id.0), &limit, &offset],
+        )?;
+
+        let mut results = vec![];
+        for row in rows.iter() {
+            let user_id: String = row.get(0);
+            match u64::from_str(&user_id) {
+                Ok(id) => results.push(XpStatistics {
+                    user_id: UserId(id),
+                    username: row.get(2),
+                    xp: row.get(1),
+                }),
+                Err(_) => error!("Failed to parse id {} to int.", user_id),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Gets the roles given for reaching levels in a guild, by level.
+    pub fn get_level_roles(&mut self, guild_id: GuildId) -> Result<Vec<LevelRole>, CommandError> {
         let rows = self.get_conn().query(
-            "SELECT id, user_id, message, bookmark, server_id FROM reminders WHERE date < current_timestamp",
-            &[],
+            "SELECT level, role_id FROM level_roles WHERE guild_id = $1 ORDER BY level",
+            &[&format!("{}", guild_id.0)],
         )?;
 
-        let result: Vec<Reminder> = rows.into_iter()
-            .map(|row| Reminder {
-                id: row.get(0),
-                user_id: row.get(1),
-                message: row.get(2),
-                bookmark: row.get(3),
-                server_id: row.get(4),
-            })
-            .collect();
+        let mut results = vec![];
+        for row in rows.iter() {
+            let role_id: String = row.get(1);
+            match u64::from_str(&role_id) {
+                Ok(id) => results.push(LevelRole {
+                    level: row.get(0),
+                    role_id: RoleId(id),
+                }),
+                Err(_) => error!("Failed to parse id {} to int.", role_id),
+            }
+        }
 
-        Ok(result)
+        Ok(results)
     }
 
-    pub fn delete_reminder(&mut self, id: i32) -> Result<(), CommandError> {
-        self.get_conn()
-            .execute("DELETE FROM reminders WHERE id = $1", &[&id])?;
+    /// Sets the role given for reaching `level`, replacing any set before.
+    pub fn set_level_role(&mut self, guild_id: GuildId, level: i32, role_id: RoleId) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "INSERT INTO level_roles (guild_id, level, role_id) VALUES ($1, $2, $3)
+            ON CONFLICT(guild_id, level) DO UPDATE SET role_id = EXCLUDED.role_id",
+            &[&format!("{}", guild_id.0), &level, &format!("{}", role_id.0)],
+        )?;
+
         Ok(())
     }
-}
 
-impl Default for ConnectionPool {
-    fn default() -> Self {
-        ConnectionPool::new()
+    /// Stops giving a role for reaching `level`. Returns whether one was given.
+    pub fn remove_level_role(&mut self, guild_id: GuildId, level: i32) -> Result<bool, CommandError> {
+        let removed = self.get_conn().execute(
+            "DELETE FROM level_roles WHERE guild_id = $1 AND level = $2",
+            &[&format!("{}", guild_id.0), &level],
+        )?;
+
+        Ok(removed > 0)
     }
-}
 
-impl Key for ConnectionPool {
-    type Value = ConnectionPool;
-}
+    pub fn get_level_announcement_channel(&mut self, guild_id: GuildId) -> Result<Option<ChannelId>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT channel_id FROM level_announcements WHERE guild_id = $1",
+            &[&format!("{}", guild_id.0)],
+        )?;
 
-#[derive(Debug)]
-pub struct Statistics {
-    pub user_id: guild::Member,
-    pub messages: i64,
-    pub words: i64,
-}
+        Ok(rows
+            .iter()
+            .next()
+            .and_then(|row| u64::from_str(&row.get::<_, String>(0)).ok())
+            .map(ChannelId))
+    }
 
-#[derive(Debug)]
-pub struct Reminder {
-    pub id: i32,
-    pub user_id: String,
-    pub message: Option<String>,
-    pub bookmark: String,
-    pub server_id: Option<String>,
+    /// Sets the channel level-ups are announced in, or stops announcing them.
+    pub fn set_level_announcement_channel(
+        &mut self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<(), CommandError> {
+        let guild_id = format!("{}", guild_id.0);
+        match channel_id {
+            Some(channel_id) => self.get_conn().execute(
+                "INSERT INTO level_announcements (guild_id, channel_id) VALUES ($1, $2)
+                ON CONFLICT(guild_id) DO UPDATE SET channel_id = EXCLUDED.channel_id",
+                &[&guild_id, &format!("{}", channel_id.0)],
+            )?,
+            None => self.get_conn().execute(
+                "DELETE FROM level_announcements WHERE guild_id = $1",
+                &[&guild_id],
+            )?,
+        };
+
+        Ok(())
+    }
+
+    /// Gets the running totals of messages and words of a member since they
+    /// were first counted, and their streaks of days posted on as of `today`.
+    pub fn get_lifetime_statistics(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        today: NaiveDate,
+    ) -> Result<LifetimeStatistics, CommandError> {
+        // The streak is still going if they posted today or yesterday.
+        let rows = self.get_conn().query(
+            "SELECT COALESCE(t.messages, 0), COALESCE(t.words, 0),
+                    COALESCE(CASE WHEN s.last_active >= $3::DATE - 1
+                                  THEN s.last_active - s.started + 1 ELSE 0 END, 0),
+                    COALESCE(s.longest, 0)
+             FROM (SELECT $1::VARCHAR AS guild_id, $2::VARCHAR AS user_id) member
+             LEFT JOIN member_totals t USING (guild_id, user_id)
+             LEFT JOIN member_streaks s USING (guild_id, user_id)",
+            &[&format!("{}", guild_id.0), &format!("{}", user_id.0), &today],
+        )?;
+
+        let row = rows.get(0);
+        Ok(LifetimeStatistics {
+            messages: row.get(0),
+            words: row.get(1),
+            streak: i64::from(row.get::<_, i32>(2)),
+            longest_streak: i64::from(row.get::<_, i32>(3)),
+        })
+    }
+
+    /// Records that a member reached a milestone. Returns whether they hadn't
+    /// reached it before.
+    pub fn add_milestone(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        milestone: &str,
+    ) -> Result<bool, CommandError> {
+        let added = self.get_conn().execute(
+            "INSERT INTO milestones (guild_id, user_id, milestone) VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING",
+            &[&format!("{}", guild_id.0), &format!("{}", user_id.0), &milestone],
+        )?;
+
+        Ok(added > 0)
+    }
+
+    pub fn get_milestone_announcement_channel(
+        &mut self,
+        guild_id: GuildId,
+    ) -> Result<Option<ChannelId>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT channel_id FROM milestone_announcements WHERE guild_id = $1",
+            &[&format!("{}", guild_id.0)],
+        )?;
+
+        Ok(rows
+            .iter()
+            .next()
+            .and_then(|row| u64::from_str(&row.get::<_, String>(0)).ok())
+            .map(ChannelId))
+    }
+
+    /// Sets the channel milestones are announced in, or stops announcing them.
+    pub fn set_milestone_announcement_channel(
+        &mut self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<(), CommandError> {
+        let guild_id = format!("{}", guild_id.0);
+        match channel_id {
+            Some(channel_id) => self.get_conn().execute(
+                "INSERT INTO milestone_announcements (guild_id, channel_id) VALUES ($1, $2)
+                ON CONFLICT(guild_id) DO UPDATE SET channel_id = EXCLUDED.channel_id",
+                &[&guild_id, &format!("{}", channel_id.0)],
+            )?,
+            None => self.get_conn().execute(
+                "DELETE FROM milestone_announcements WHERE guild_id = $1",
+                &[&guild_id],
+            )?,
+        };
+
+        Ok(())
+    }
+
+    pub fn add_reminder(&mut self, reminder: &NewReminder) -> Result<i32, CommandError> {
+        let guild_id = reminder.guild_id.map(|g| format!("{}", g.0));
+        let recurrence = reminder.recurrence.as_ref().map(|r| r.to_string());
+        let rows = self.get_conn().query(
+            "INSERT INTO reminders
+                (user_id, date, message, bookmark, guild_id, channel_id, message_id, recurrence, in_channel)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id",
+            &[
+                &format!("{}", reminder.user_id.0),
+                &reminder.date,
+                &reminder.message,
+                &reminder.bookmark,
+                &guild_id,
+                &format!("{}", reminder.channel_id.0),
+                &format!("{}", reminder.message_id.0),
+                &recurrence,
+                &reminder.in_channel,
+            ],
+        )?;
+
+        Ok(rows.get(0).get(0))
+    }
+
+    /// Marks all reminders that are due by `now`, or due for another attempt,
+    /// as being sent and returns them.
+    pub fn claim_due_reminders(&mut self, now: NaiveDateTime) -> Result<Vec<Reminder>, CommandError> {
+        let rows = self.get_conn().query(
+            &format!(
+                "WITH claimed AS (
+                    UPDATE reminders SET state = $1
+                    WHERE state IN ($2, $3) AND COALESCE(retry_at, date) <= $4
+                    RETURNING *
+                )
+                SELECT {} FROM claimed r LEFT JOIN timezones t ON t.user_id = r.user_id",
+                REMINDER_COLUMNS
+            ),
+            &[
+                &DeliveryState::Sending.as_str(),
+                &DeliveryState::Pending.as_str(),
+                &DeliveryState::Failed.as_str(),
+                &now,
+            ],
+        )?;
+
+        Ok(rows.iter().map(|row| read_reminder(&row)).collect())
+    }
+
+    /// Adds people or roles to be reminded instead of the owner of a reminder.
+    pub fn add_reminder_recipients(
+        &mut self,
+        reminder_id: i32,
+        recipients: &[Recipient],
+    ) -> Result<(), CommandError> {
+        let conn = self.get_conn();
+        for recipient in recipients {
+            let (user_id, role_id) = match *recipient {
+                Recipient::User(id) => (Some(format!("{}", id.0)), None),
+                Recipient::Role(id) => (None, Some(format!("{}", id.0))),
+            };
+            conn.execute(
+                "INSERT INTO reminder_recipients (reminder_id, user_id, role_id) VALUES ($1, $2, $3)
+                ON CONFLICT DO NOTHING",
+                &[&reminder_id, &user_id, &role_id],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets everyone to remind for a reminder. If there's nobody, the owner of
+    /// the reminder should be reminded.
+    pub fn get_reminder_recipients(&mut self, reminder_id: i32) -> Result<Vec<Recipient>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT user_id, role_id FROM reminder_recipients WHERE reminder_id = $1",
+            &[&reminder_id],
+        )?;
+
+        let mut results = vec![];
+        for row in rows.iter() {
+            let user_id: Option<String> = row.get(0);
+            let role_id: Option<String> = row.get(1);
+            let recipient = match (user_id, role_id) {
+                (Some(id), _) => u64::from_str(&id).ok().map(|id| Recipient::User(UserId(id))),
+                (None, Some(id)) => u64::from_str(&id).ok().map(|id| Recipient::Role(RoleId(id))),
+                (None, None) => None,
+            };
+            match recipient {
+                Some(r) => results.push(r),
+                None => error!("Invalid recipient for reminder {}.", reminder_id),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Sets the message people react to in order to join a reminder.
+    pub fn set_reminder_join_message(
+        &mut self,
+        reminder_id: i32,
+        message_id: MessageId,
+    ) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "UPDATE reminders SET join_message_id = $2 WHERE id = $1",
+            &[&reminder_id, &format!("{}", message_id.0)],
+        )?;
+        Ok(())
+    }
+
+    /// Adds a user to the reminder joined through `message_id`, if it isn't due
+    /// by `now`. Returns whether the user was added.
+    pub fn join_reminder(
+        &mut self,
+        message_id: MessageId,
+        user_id: UserId,
+        now: NaiveDateTime,
+    ) -> Result<bool, CommandError> {
+        let added = self.get_conn().execute(
+            "INSERT INTO reminder_recipients (reminder_id, user_id)
+            SELECT id, $2 FROM reminders
+            WHERE join_message_id = $1 AND state = $3 AND date > $4
+            ON CONFLICT DO NOTHING",
+            &[
+                &format!("{}", message_id.0),
+                &format!("{}", user_id.0),
+                &DeliveryState::Pending.as_str(),
+                &now,
+            ],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Removes a user from the reminder joined through `message_id`.
+    pub fn leave_reminder(&mut self, message_id: MessageId, user_id: UserId) -> Result<bool, CommandError> {
+        let removed = self.get_conn().execute(
+            "DELETE FROM reminder_recipients
+            WHERE user_id = $2
+              AND reminder_id IN (SELECT id FROM reminders WHERE join_message_id = $1)",
+            &[&format!("{}", message_id.0), &format!("{}", user_id.0)],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Gets the times at which all undelivered reminders are next due.
+    pub fn get_reminder_due_times(&mut self) -> Result<Vec<NaiveDateTime>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT DISTINCT COALESCE(retry_at, date) FROM reminders WHERE state IN ($1, $2)",
+            &[&DeliveryState::Pending.as_str(), &DeliveryState::Failed.as_str()],
+        )?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    /// Puts reminders that were being sent when the bot stopped back in line,
+    /// so that they are delivered at least once.
+    pub fn reset_sending_reminders(&mut self) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "UPDATE reminders SET state = $1 WHERE state = $2",
+            &[&DeliveryState::Pending.as_str(), &DeliveryState::Sending.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Gets all pending reminders belonging to `user_id`, soonest first.
+    pub fn get_user_reminders(&mut self, user_id: UserId) -> Result<Vec<Reminder>, CommandError> {
+        let rows = self.get_conn().query(
+            &format!(
+                "SELECT {} FROM reminders r LEFT JOIN timezones t ON t.user_id = r.user_id
+                 WHERE r.user_id = $1 AND r.state <> $2
+                 ORDER BY r.date",
+                REMINDER_COLUMNS
+            ),
+            &[&format!("{}", user_id.0), &DeliveryState::Delivered.as_str()],
+        )?;
+
+        Ok(rows.iter().map(|row| read_reminder(&row)).collect())
+    }
+
+    /// Moves a recurring reminder on to its next occurrence.
+    pub fn reschedule_reminder(&mut self, id: i32, date: NaiveDateTime) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "UPDATE reminders SET date = $2, state = $3, attempts = 0, retry_at = NULL
+             WHERE id = $1",
+            &[&id, &date, &DeliveryState::Pending.as_str()],
+        )?;
+        Ok(())
+    }
+
+    pub fn mark_reminder_delivered(&mut self, id: i32) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "UPDATE reminders SET state = $2 WHERE id = $1",
+            &[&id, &DeliveryState::Delivered.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Records a failed delivery attempt and when to try again.
+    pub fn retry_reminder(
+        &mut self,
+        id: i32,
+        attempts: i32,
+        retry_at: NaiveDateTime,
+    ) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "UPDATE reminders SET state = $2, attempts = $3, retry_at = $4 WHERE id = $1",
+            &[&id, &DeliveryState::Failed.as_str(), &attempts, &retry_at],
+        )?;
+        Ok(())
+    }
+
+    /// Moves a reminder that can't be delivered to `failed_reminders`.
+    pub fn dead_letter_reminder(
+        &mut self,
+        id: i32,
+        attempts: i32,
+        error: &str,
+    ) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "WITH moved AS (
+                DELETE FROM reminders WHERE id = $1
+                RETURNING id, user_id, date, message, guild_id, channel_id, recurrence
+            )
+            INSERT INTO failed_reminders
+                (id, user_id, date, message, guild_id, channel_id, recurrence, attempts, error)
+            SELECT id, user_id, date, message, guild_id, channel_id, recurrence, $2, $3 FROM moved",
+            &[&id, &attempts, &error],
+        )?;
+        Ok(())
+    }
+
+    /// Records that one occurrence of a recurring reminder can't be delivered
+    /// in `failed_reminders`, and moves the reminder on to its next occurrence.
+    pub fn skip_failed_occurrence(
+        &mut self,
+        id: i32,
+        attempts: i32,
+        error: &str,
+        next: NaiveDateTime,
+    ) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "WITH failed AS (
+                INSERT INTO failed_reminders
+                    (id, user_id, date, message, guild_id, channel_id, recurrence, attempts, error)
+                SELECT id, user_id, date, message, guild_id, channel_id, recurrence, $2, $3
+                FROM reminders WHERE id = $1
+                ON CONFLICT (id, date) DO NOTHING
+            )
+            UPDATE reminders SET date = $4, state = $5, attempts = 0, retry_at = NULL
+            WHERE id = $1",
+            &[&id, &attempts, &error, &next, &DeliveryState::Pending.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Deletes delivered reminders once they're `days` old.
+    pub fn purge_delivered_reminders(&mut self, days: i32) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "DELETE FROM reminders
+             WHERE state = $1 AND date < current_timestamp - make_interval(days => $2)",
+            &[&DeliveryState::Delivered.as_str(), &days],
+        )?;
+        Ok(())
+    }
+
+    /// Gets the most recent reminders from a guild that could not be delivered.
+    pub fn get_failed_reminders(
+        &mut self,
+        guild_id: GuildId,
+        limit: i64,
+    ) -> Result<Vec<FailedReminder>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT f.id, f.user_id, f.date, f.message, f.attempts, f.error, f.failed_at,
+                    f.recurrence, t.timezone
+             FROM failed_reminders f LEFT JOIN timezones t ON t.user_id = f.user_id
+             WHERE f.guild_id = $1
+             ORDER BY f.failed_at DESC
+             LIMIT $2",
+            &[&format!("{}", guild_id.0), &limit],
+        )?;
+
+        Ok(rows.iter().map(|row| read_failed_reminder(&row)).collect())
+    }
+
+    /// Gets the most recent reminders of `user_id` that could not be
+    /// delivered, wherever they were set.
+    pub fn get_user_failed_reminders(
+        &mut self,
+        user_id: UserId,
+        limit: i64,
+    ) -> Result<Vec<FailedReminder>, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT f.id, f.user_id, f.date, f.message, f.attempts, f.error, f.failed_at,
+                    f.recurrence, t.timezone
+             FROM failed_reminders f LEFT JOIN timezones t ON t.user_id = f.user_id
+             WHERE f.user_id = $1
+             ORDER BY f.failed_at DESC
+             LIMIT $2",
+            &[&format!("{}", user_id.0), &limit],
+        )?;
+
+        Ok(rows.iter().map(|row| read_failed_reminder(&row)).collect())
+    }
+
+    /// Deletes a reminder by its id or bookmark, but only if it belongs to `user_id`.
+    /// Returns whether a reminder was deleted.
+    pub fn delete_user_reminder(&mut self, user_id: UserId, key: &str) -> Result<bool, CommandError> {
+        let deleted = self.get_conn().execute(
+            "DELETE FROM reminders
+             WHERE user_id = $1 AND (bookmark = $2 OR CAST(id AS TEXT) = $2) AND state IN ($3, $4)",
+            &[
+                &format!("{}", user_id.0),
+                &key,
+                &DeliveryState::Pending.as_str(),
+                &DeliveryState::Failed.as_str(),
+            ],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Whether a reminder of `user_id`, found by id or bookmark, is being sent
+    /// right now, and so can't be changed.
+    pub fn is_user_reminder_sending(&mut self, user_id: UserId, key: &str) -> Result<bool, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT 1 FROM reminders
+             WHERE user_id = $1 AND (bookmark = $2 OR CAST(id AS TEXT) = $2) AND state = $3",
+            &[&format!("{}", user_id.0), &key, &DeliveryState::Sending.as_str()],
+        )?;
+        Ok(!rows.is_empty())
+    }
+
+    /// Moves a reminder, found by id or bookmark, to a new time, keeping how it
+    /// repeats and its text unless new ones are given, but only if it belongs
+    /// to `user_id`. Returns whether a reminder was changed.
+    pub fn update_user_reminder_date(
+        &mut self,
+        user_id: UserId,
+        key: &str,
+        date: NaiveDateTime,
+        recurrence: &Option<Recurrence>,
+        message: Option<&str>,
+    ) -> Result<bool, CommandError> {
+        let recurrence = recurrence.as_ref().map(|r| r.to_string());
+        let updated = self.get_conn().execute(
+            "UPDATE reminders SET date = $3, recurrence = COALESCE($4, recurrence),
+                message = COALESCE($5, message), state = $6, attempts = 0, retry_at = NULL
+             WHERE user_id = $1 AND (bookmark = $2 OR CAST(id AS TEXT) = $2) AND state IN ($6, $7)",
+            &[
+                &format!("{}", user_id.0),
+                &key,
+                &date,
+                &recurrence,
+                &message,
+                &DeliveryState::Pending.as_str(),
+                &DeliveryState::Failed.as_str(),
+            ],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Changes the text of a reminder, found by id or bookmark, but only if it
+    /// belongs to `user_id`. Returns whether a reminder was changed.
+    pub fn update_user_reminder_message(
+        &mut self,
+        user_id: UserId,
+        key: &str,
+        message: &str,
+    ) -> Result<bool, CommandError> {
+        let updated = self.get_conn().execute(
+            "UPDATE reminders SET message = $3
+             WHERE user_id = $1 AND (bookmark = $2 OR CAST(id AS TEXT) = $2) AND state IN ($4, $5)",
+            &[
+                &format!("{}", user_id.0),
+                &key,
+                &message,
+                &DeliveryState::Pending.as_str(),
+                &DeliveryState::Failed.as_str(),
+            ],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn set_timezone(&mut self, user_id: UserId, timezone: Tz) -> Result<(), CommandError> {
+        self.get_conn().execute(
+            "INSERT INTO timezones (user_id, timezone) VALUES ($1, $2)
+            ON CONFLICT(user_id) DO UPDATE SET timezone = $2",
+            &[&format!("{}", user_id.0), &timezone.name()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Gets the timezone a user has set, or UTC if they haven't set one.
+    pub fn get_timezone(&mut self, user_id: UserId) -> Result<Tz, CommandError> {
+        let rows = self.get_conn().query(
+            "SELECT timezone FROM timezones WHERE user_id = $1",
+            &[&format!("{}", user_id.0)],
+        )?;
+
+        Ok(parse_timezone(rows.iter().next().map(|row| row.get(0))))
+    }
+}
+
+/// Reads a row of date, messages and words.
+fn read_daily_statistics(row: &Row) -> DailyStatistics {
+    DailyStatistics {
+        date: row.get(0),
+        messages: row.get(1),
+        words: row.get(2),
+    }
+}
+
+/// Reads a reminder from a row selected with `REMINDER_COLUMNS`.
+fn read_reminder(row: &Row) -> Reminder {
+    Reminder {
+        id: row.get(0),
+        user_id: row.get(1),
+        message: row.get(2),
+        bookmark: row.get(3),
+        guild_id: row.get(4),
+        date: row.get(5),
+        timezone: parse_timezone(row.get(6)),
+        recurrence: read_recurrence(row.get(7)),
+        channel_id: row.get(8),
+        in_channel: row.get(9),
+        attempts: row.get(10),
+        message_id: row.get(11),
+    }
+}
+
+/// Reads a reminder from a row of `failed_reminders` `f` joined with timezones `t`.
+fn read_failed_reminder(row: &Row) -> FailedReminder {
+    FailedReminder {
+        id: row.get(0),
+        user_id: row.get(1),
+        date: row.get(2),
+        message: row.get(3),
+        attempts: row.get(4),
+        error: row.get(5),
+        failed_at: row.get(6),
+        recurrence: read_recurrence(row.get(7)),
+        timezone: parse_timezone(row.get(8)),
+    }
+}
+
+/// Parses a timezone name from the database, falling back to UTC.
+fn parse_timezone(name: Option<String>) -> Tz {
+    match name {
+        Some(name) => Tz::from_str(&name).unwrap_or_else(|_| {
+            error!("Unknown timezone {} in database.", name);
+            Tz::UTC
+        }),
+        None => Tz::UTC,
+    }
+}
+
+/// Parses a stored recurrence, dropping it if it can no longer be understood.
+fn read_recurrence(recurrence: Option<String>) -> Option<Recurrence> {
+    recurrence.and_then(|r| match Recurrence::from_str(&r) {
+        Ok(recurrence) => Some(recurrence),
+        Err(why) => {
+            error!("Invalid recurrence {} in database: {}", r, why);
+            None
+        }
+    })
+}
+
+impl Default for ConnectionPool {
+    fn default() -> Self {
+        ConnectionPool::new()
+    }
+}
+
+impl Key for ConnectionPool {
+    type Value = ConnectionPool;
+}
+
+/// What the most active users are ranked by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatisticsOrder {
+    Messages,
+    Words,
+    WordsPerMessage,
+}
+
+impl StatisticsOrder {
+    fn sql(&self) -> &'static str {
+        match *self {
+            StatisticsOrder::Messages => "SUM(messages)",
+            StatisticsOrder::Words => "SUM(words)",
+            StatisticsOrder::WordsPerMessage => "CAST(SUM(words) AS FLOAT) / GREATEST(SUM(messages), 1)",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StatisticsOrder::Messages => "messages",
+            StatisticsOrder::Words => "words",
+            StatisticsOrder::WordsPerMessage => "words per message",
+        }
+    }
+}
+
+/// What message and word counts are kept per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StatisticsKey {
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub user_id: UserId,
+    /// The start of the hour, in UTC.
+    pub hour: NaiveDateTime,
+}
+
+/// Messages and words to add to the statistics of a user on a day.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StatisticsDelta {
+    pub messages: i32,
+    pub words: i32,
+}
+
+impl StatisticsDelta {
+    pub fn add(&mut self, other: &StatisticsDelta) {
+        self.messages += other.messages;
+        self.words += other.words;
+    }
+}
+
+/// What the use of custom emoji is counted per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EmojiKey {
+    pub guild_id: GuildId,
+    pub emoji_id: EmojiId,
+    /// The UTC day.
+    pub date: NaiveDate,
+}
+
+/// Messages and reactions to add to the statistics of an emoji on a day.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EmojiDelta {
+    pub messages: i32,
+    pub reactions: i32,
+}
+
+impl EmojiDelta {
+    pub fn add(&mut self, other: &EmojiDelta) {
+        self.messages += other.messages;
+        self.reactions += other.reactions;
+    }
+}
+
+/// Everything counted for the statistics that hasn't been written yet.
+#[derive(Debug, Default)]
+pub struct PendingStatistics {
+    pub counts: HashMap<StatisticsKey, StatisticsDelta>,
+    /// The latest username of everyone counted.
+    pub usernames: HashMap<UserId, String>,
+    pub emoji: HashMap<EmojiKey, EmojiDelta>,
+    /// XP earned per guild and user.
+    pub xp: HashMap<(GuildId, UserId), i64>,
+}
+
+impl PendingStatistics {
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty() && self.usernames.is_empty() && self.emoji.is_empty() && self.xp.is_empty()
+    }
+
+    /// Adds the counts of `older`. Usernames that are here already are newer,
+    /// so they're kept.
+    pub fn merge(&mut self, older: PendingStatistics) {
+        for (key, delta) in older.counts {
+            self.counts.entry(key).or_insert_with(StatisticsDelta::default).add(&delta);
+        }
+        for (user_id, username) in older.usernames {
+            self.usernames.entry(user_id).or_insert(username);
+        }
+        for (key, delta) in older.emoji {
+            self.emoji.entry(key).or_insert_with(EmojiDelta::default).add(&delta);
+        }
+        for (key, xp) in older.xp {
+            *self.xp.entry(key).or_insert(0) += xp;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Statistics {
+    pub user_id: UserId,
+    /// The last known username, if they've been seen since it was stored.
+    pub username: Option<String>,
+    pub messages: i64,
+    pub words: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyStatistics {
+    pub date: NaiveDate,
+    pub messages: i64,
+    pub words: i64,
+}
+
+#[derive(Debug)]
+pub struct UserDailyStatistics {
+    pub user_id: UserId,
+    pub date: NaiveDate,
+    pub messages: i64,
+    pub words: i64,
+    pub username: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ChannelStatistics {
+    pub channel_id: ChannelId,
+    pub messages: i64,
+    pub words: i64,
+}
+
+#[derive(Debug)]
+pub struct EmojiStatistics {
+    pub emoji_id: EmojiId,
+    pub messages: i64,
+    pub reactions: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemberStatistics {
+    pub joins: i64,
+    pub leaves: i64,
+    /// Different members who joined, as some may join more than once.
+    pub new_members: i64,
+    /// New members who posted within a week of joining.
+    pub posted_first_week: i64,
+}
+
+/// The activity of a member since they were first counted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LifetimeStatistics {
+    pub messages: i64,
+    pub words: i64,
+    /// Days in a row they posted on, up to today or yesterday.
+    pub streak: i64,
+    pub longest_streak: i64,
+}
+
+/// How the XP of a member changed when earned XP was written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XpChange {
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    pub old_xp: i64,
+    pub new_xp: i64,
+}
+
+#[derive(Debug)]
+pub struct XpStatistics {
+    pub user_id: UserId,
+    /// See `Statistics::username`.
+    pub username: Option<String>,
+    pub xp: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevelRole {
+    pub level: i32,
+    pub role_id: RoleId,
+}
+
+#[derive(Debug)]
+pub struct VoiceStatistics {
+    pub user_id: UserId,
+    /// See `Statistics::username`.
+    pub username: Option<String>,
+    pub seconds: i64,
+}
+
+#[derive(Debug)]
+pub struct Reminder {
+    pub id: i32,
+    pub user_id: String,
+    pub message: Option<String>,
+    pub bookmark: String,
+    pub guild_id: Option<String>,
+    pub date: NaiveDateTime,
+    pub timezone: Tz,
+    pub recurrence: Option<Recurrence>,
+    pub channel_id: Option<String>,
+    pub in_channel: bool,
+    /// Failed delivery attempts so far.
+    pub attempts: i32,
+    /// The message that created the reminder, missing for old reminders.
+    pub message_id: Option<String>,
+}
+
+/// Where a reminder is in its delivery. Reminders start out `Pending`, are
+/// `Sending` while the watcher works on them, and end up `Delivered`, or
+/// `Failed` until they are retried or given up on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeliveryState {
+    Pending,
+    Sending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryState {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            DeliveryState::Pending => "pending",
+            DeliveryState::Sending => "sending",
+            DeliveryState::Delivered => "delivered",
+            DeliveryState::Failed => "failed",
+        }
+    }
+}
+
+/// A reminder that was given up on, from `failed_reminders`.
+#[derive(Debug)]
+pub struct FailedReminder {
+    pub id: i32,
+    pub user_id: String,
+    pub date: NaiveDateTime,
+    pub message: Option<String>,
+    pub attempts: i32,
+    pub error: String,
+    pub failed_at: NaiveDateTime,
+    pub recurrence: Option<Recurrence>,
+    /// The timezone of the owner, to show the dates in.
+    pub timezone: Tz,
+}
+
+/// Someone to remind other than the owner of a reminder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Recipient {
+    User(UserId),
+    Role(RoleId),
+}
+
+/// A reminder to be stored with `add_reminder`.
+#[derive(Debug)]
+pub struct NewReminder<'a> {
+    pub user_id: UserId,
+    pub guild_id: Option<GuildId>,
+    /// The channel the reminder was created in.
+    pub channel_id: ChannelId,
+    /// The message the reminder was created with.
+    pub message_id: MessageId,
+    pub date: NaiveDateTime,
+    pub message: &'a str,
+    pub bookmark: &'a str,
+    pub recurrence: Option<Recurrence>,
+    /// Whether to post the reminder in `channel_id` rather than DM'ing the user.
+    pub in_channel: bool,
 }
@@ -0,0 +1,186 @@
+use chrono::Duration;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use serenity::model::misc::Mentionable;
+use std::cmp;
+use std::env;
+use typemap::Key;
+
+use command_error::CommandError;
+use connectionpool::{ConnectionPool, XpChange};
+
+/// The highest level, so that a tiny base or exponent can't make levels
+/// endless.
+const MAX_LEVEL: i64 = 1_000_000;
+
+/// How XP is earned and how much each level takes, set by `XP_PER_MESSAGE`,
+/// `XP_COOLDOWN_SECS`, `XP_LEVEL_BASE` and `XP_LEVEL_EXPONENT`.
+/// Reaching level `n` takes `base * n ^ exponent` XP in total.
+#[derive(Debug, Clone, Copy)]
+pub struct LevelConfig {
+    pub xp_per_message: i64,
+    /// How long after earning XP a member has to wait to earn more, so
+    /// spamming doesn't pay off.
+    pub cooldown: Duration,
+    pub base: f64,
+    pub exponent: f64,
+}
+
+impl LevelConfig {
+    pub fn from_env() -> LevelConfig {
+        LevelConfig {
+            xp_per_message: number_from_env("XP_PER_MESSAGE", 20.0) as i64,
+            cooldown: Duration::seconds(number_from_env("XP_COOLDOWN_SECS", 60.0) as i64),
+            base: number_from_env("XP_LEVEL_BASE", 100.0),
+            exponent: number_from_env("XP_LEVEL_EXPONENT", 1.5),
+        }
+    }
+
+    /// The XP it takes in total to reach `level`.
+    pub fn xp_for_level(&self, level: i64) -> i64 {
+        (self.base * (level as f64).powf(self.exponent)).round() as i64
+    }
+
+    /// The level reached with `xp` XP.
+    pub fn level_for_xp(&self, xp: i64) -> i64 {
+        if xp <= 0 {
+            return 0;
+        }
+        let estimate = (xp as f64 / self.base).powf(1.0 / self.exponent);
+        let mut level = cmp::min(estimate as i64, MAX_LEVEL);
+        // The estimate can be off by one where `xp_for_level` rounds.
+        while level > 0 && self.xp_for_level(level) > xp {
+            level -= 1;
+        }
+        while level < MAX_LEVEL && self.xp_for_level(level + 1) <= xp {
+            level += 1;
+        }
+        level
+    }
+}
+
+impl Key for LevelConfig {
+    type Value = LevelConfig;
+}
+
+fn number_from_env(name: &str, default: f64) -> f64 {
+    match env::var(name) {
+        Ok(number) => match number.parse() {
+            Ok(number) if number > 0.0 => number,
+            _ => {
+                error!("Expected {} to be a positive number, using {} instead.", name, default);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// Gives the roles of the levels reached to members who went up a level,
+/// and announces it if the guild wants level-ups announced.
+pub fn reward_level_ups(pool: &mut ConnectionPool, config: &LevelConfig, changes: &[XpChange]) {
+    for change in changes {
+        let old_level = config.level_for_xp(change.old_xp);
+        let level = config.level_for_xp(change.new_xp);
+        if level <= old_level {
+            continue;
+        }
+
+        let roles = match reward_roles(pool, change.guild_id, change.user_id, level) {
+            Ok(roles) => roles,
+            Err(why) => {
+                error!("Failed to give level roles: {}", why);
+                vec![]
+            }
+        };
+        if let Err(why) = announce_level_up(pool, change.guild_id, change.user_id, level, &roles) {
+            error!("Failed to announce level up: {}", why);
+        }
+    }
+}
+
+/// Gives a member the roles of every level up to `level` that they don't
+/// have yet, and returns the ones given.
+fn reward_roles(
+    pool: &mut ConnectionPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    level: i64,
+) -> Result<Vec<RoleId>, CommandError> {
+    let roles: Vec<RoleId> = pool
+        .get_level_roles(guild_id)?
+        .into_iter()
+        .filter(|r| i64::from(r.level) <= level)
+        .map(|r| r.role_id)
+        .collect();
+    if roles.is_empty() {
+        return Ok(roles);
+    }
+
+    let mut member = guild_id.member(user_id)?;
+    let missing: Vec<RoleId> = roles.into_iter().filter(|r| !member.roles.contains(r)).collect();
+    if !missing.is_empty() {
+        member.add_roles(&missing)?;
+    }
+    Ok(missing)
+}
+
+fn announce_level_up(
+    pool: &mut ConnectionPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    level: i64,
+    roles: &[RoleId],
+) -> Result<(), CommandError> {
+    let channel_id = match pool.get_level_announcement_channel(guild_id)? {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    let mut text = format!("Congratulations {}, you reached level {}!", user_id.mention(), level);
+    if !roles.is_empty() {
+        let names: Vec<String> = roles
+            .iter()
+            .map(|r| r.find().map(|r| r.name).unwrap_or_else(|| format!("{}", r)))
+            .collect();
+        text.push_str(&format!(" You're now **{}**.", names.join("**, **")));
+    }
+    channel_id.say(&text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LevelConfig {
+        LevelConfig {
+            xp_per_message: 20,
+            cooldown: Duration::seconds(60),
+            base: 100.0,
+            exponent: 1.5,
+        }
+    }
+
+    #[test]
+    fn levels_follow_the_curve() {
+        let config = config();
+        assert_eq!(config.xp_for_level(1), 100);
+        assert_eq!(config.xp_for_level(4), 800);
+        assert_eq!(config.level_for_xp(0), 0);
+        assert_eq!(config.level_for_xp(99), 0);
+        assert_eq!(config.level_for_xp(100), 1);
+        assert_eq!(config.level_for_xp(799), 3);
+        assert_eq!(config.level_for_xp(800), 4);
+        for level in 0..200 {
+            let xp = config.xp_for_level(level);
+            assert_eq!(config.level_for_xp(xp), level);
+            assert_eq!(config.level_for_xp(xp - 1), cmp::max(level - 1, 0));
+        }
+    }
+
+    #[test]
+    fn levels_are_capped() {
+        let config = LevelConfig { base: 0.001, exponent: 0.1, ..config() };
+        assert_eq!(config.level_for_xp(i64::max_value()), MAX_LEVEL);
+    }
+}
@@ -1,27 +1,49 @@
 extern crate chrono;
+extern crate chrono_tz;
+extern crate ctrlc;
 extern crate env_logger;
 #[macro_use]
 extern crate log;
+extern crate plotters;
+extern crate png;
 extern crate postgres;
 extern crate r2d2;
 extern crate r2d2_postgres;
 extern crate rand;
 #[macro_use]
+extern crate serde_json;
+#[macro_use]
 extern crate serenity;
 extern crate typemap;
 
+mod chart;
 mod command_error;
 mod commands;
 mod connectionpool;
+mod levels;
+mod migrations;
+mod milestones;
+mod reminderqueue;
+mod statsaggregator;
+mod statsretention;
+mod timeparse;
 mod util;
 
+use chrono::offset::Utc;
 use serenity::framework::standard::{help_commands, DispatchError, HelpBehaviour, StandardFramework};
-use serenity::model::{Permissions, gateway::Ready};
+use serenity::model::{Permissions, channel::{Message, Reaction}, gateway::Ready};
+use serenity::model::{guild::{Guild, Member}, id::GuildId, user::User, voice::VoiceState};
 use serenity::prelude::*;
 use std::{env, thread};
+use std::collections::HashMap;
 
 use commands::*;
+use commands::leaderboard::Leaderboards;
 use connectionpool::ConnectionPool;
+use levels::LevelConfig;
+use reminderqueue::ReminderQueue;
+use statsaggregator::StatsAggregator;
+use statsretention::Retention;
 
 struct Handler;
 
@@ -29,46 +51,102 @@ impl EventHandler for Handler {
     fn ready(&self, _: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
     }
+
+    fn guild_create(&self, ctx: Context, guild: Guild, _: bool) {
+        // Discord leaves offline members out of large guilds, so ask for them
+        // to know who is still a member.
+        if guild.large {
+            ctx.shard.chunk_guilds(vec![guild.id], None, None);
+        }
+        voice::resume_voice_sessions(&ctx, &guild);
+    }
+
+    fn guild_member_addition(&self, ctx: Context, guild_id: GuildId, member: Member) {
+        let user = member.user.read();
+        if !user.bot {
+            members::record_member_event(&ctx, guild_id, user.id, true);
+        }
+    }
+
+    fn guild_member_removal(&self, ctx: Context, guild_id: GuildId, user: User, _: Option<Member>) {
+        if !user.bot {
+            members::record_member_event(&ctx, guild_id, user.id, false);
+        }
+    }
+
+    fn message(&self, ctx: Context, msg: Message) {
+        statistics::record_message(&ctx, &msg);
+    }
+
+    fn voice_state_update(&self, ctx: Context, guild_id: Option<GuildId>, state: VoiceState) {
+        voice::update_voice_state(&ctx, guild_id, &state);
+    }
+
+    fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        remindme::join_by_reaction(&ctx, &reaction, true);
+        emoji::record_reaction(&ctx, &reaction);
+        leaderboard::turn_page(&ctx, &reaction);
+    }
+
+    fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        remindme::join_by_reaction(&ctx, &reaction, false);
+        leaderboard::turn_page(&ctx, &reaction);
+    }
 }
 
 fn main() {
     // Configure the Discord client with the bot token in the environment.
     let token = env::var("DISCORD_TOKEN").expect("Expected a Discord token in the environment");
     let mut client = Client::new(&token, Handler).expect("Error creating the Discord client");
+    let queue = ReminderQueue::new();
+    let aggregator = StatsAggregator::new();
+    let config = LevelConfig::from_env();
     {
-        let pool = ConnectionPool::new();
+        let mut pool = ConnectionPool::new();
+        pool.migrate().expect("Failed to migrate the database");
         let mut data = client.data.lock();
         data.insert::<ConnectionPool>(pool);
+        data.insert::<ReminderQueue>(queue.clone());
+        data.insert::<StatsAggregator>(aggregator.clone());
+        data.insert::<Leaderboards>(HashMap::new());
+        data.insert::<LevelConfig>(config);
     }
 
     // Run a background thread to watch for !remindme triggers
     thread::spawn(move || {
-        remindme::watch_for_reminders(ConnectionPool::new());
+        remindme::watch_for_reminders(ConnectionPool::new(), queue);
     });
 
+    // Write counted messages to the database every now and then, and reward
+    // those who went up a level.
+    {
+        let aggregator = aggregator.clone();
+        thread::spawn(move || {
+            statsaggregator::flush_periodically(ConnectionPool::new(), aggregator, config);
+        });
+    }
+
+    // Note who is still in voice every now and then, in case the bot crashes.
+    thread::spawn(move || {
+        voice::heartbeat_periodically(ConnectionPool::new());
+    });
+
+    // Roll up and prune old statistics every now and then.
+    let retention = Retention::from_env();
+    thread::spawn(move || {
+        statsretention::maintain_periodically(ConnectionPool::new(), retention);
+    });
+
+    // Stop the shards on Ctrl-C or SIGTERM, so the statistics are flushed below.
+    let shard_manager = client.shard_manager.clone();
+    ctrlc::set_handler(move || {
+        info!("Shutting down.");
+        shard_manager.lock().shutdown_all();
+    }).expect("Failed to set the shutdown handler");
+
     client.with_framework(
         StandardFramework::new()
             .configure(|c| c.prefix("?").delimiter(" "))
-            .before(|ctx, msg, _command_name| {
-                // Don't log statistics for PM's
-                let guild_id = match msg.guild_id() {
-                    Some(id) => id,
-                    None => return false,
-                };
-
-                // Push additional message and word count to db.
-                util::get_pool(ctx)
-                    .update_statistics(
-                        guild_id,
-                        msg.author.id,
-                        msg.timestamp.naive_utc().date(),
-                        1,
-                        msg.content.split_whitespace().count() as i32,
-                    )
-                    .expect("Failed to update statistics");
-
-                true
-            })
             .after(|_, _, command_name, error| match error {
                 Ok(()) => info!("Processed command '{}'", command_name),
                 Err(why) => error!("Command '{}' return error {:?}", command_name, why),
@@ -111,24 +189,78 @@ fn main() {
             .command("role", |c| {
                 c.desc("Joins or leaves a public role.")
                     .guild_only(true)
-                    .known_as("rank")
                     .cmd(roles::joinrole)
             })
             .command("remindme", |c| {
                 c.desc("Have the bot remind you of something.")
                     .cmd(remindme::remind)
             })
+            .command("remind", |c| {
+                c.desc("Have the bot remind other members or roles of something.")
+                    .guild_only(true)
+                    .required_permissions(Permissions::MANAGE_MESSAGES)
+                    .cmd(remindme::remind_others)
+            })
+            .command("reminders", |c| {
+                c.desc("Lists your pending reminders, or the ones that failed.")
+                    .cmd(remindme::reminders)
+            })
+            .command("timezone", |c| {
+                c.desc("Sets the timezone used for your reminders and dates.")
+                    .known_as("tz")
+                    .cmd(timezone::timezone)
+            })
             .command("stats", |c| {
                 c.desc("Shows some stats about the most active members.")
                     .guild_only(true)
                     .required_permissions(Permissions::ADMINISTRATOR)
                     .cmd(statistics::stats)
             })
+            .command("channelstats", |c| {
+                c.desc("Shows the most active channels.")
+                    .guild_only(true)
+                    .required_permissions(Permissions::ADMINISTRATOR)
+                    .cmd(statistics::channelstats)
+            })
+            .command("emojistats", |c| {
+                c.desc("Shows how much the emoji of the server are used.")
+                    .guild_only(true)
+                    .required_permissions(Permissions::ADMINISTRATOR)
+                    .cmd(emoji::emojistats)
+            })
+            .command("rank", |c| {
+                c.desc("Shows your level and XP, or someone else's.")
+                    .guild_only(true)
+                    .cmd(rank::rank)
+            })
+            .command("levels", |c| {
+                c.desc("Lists the members with the most XP.")
+                    .guild_only(true)
+                    .cmd(rank::levels)
+            })
+            .command("levelroles", |c| {
+                c.desc("Sets the roles given for reaching levels, and where level-ups are announced.")
+                    .guild_only(true)
+                    .required_permissions(Permissions::ADMINISTRATOR)
+                    .cmd(rank::levelroles)
+            })
+            .command("milestones", |c| {
+                c.desc("Sets the channel milestones of members are announced in.")
+                    .guild_only(true)
+                    .required_permissions(Permissions::ADMINISTRATOR)
+                    .cmd(admin::milestones)
+            })
             .command("purge", |c| {
                 c.desc("Purge a number of messages from a channel.")
                     .guild_only(true)
                     .required_permissions(Permissions::MANAGE_MESSAGES)
                     .cmd(admin::purge)
+            })
+            .command("failedreminders", |c| {
+                c.desc("Lists reminders from this server that could not be delivered.")
+                    .guild_only(true)
+                    .required_permissions(Permissions::ADMINISTRATOR)
+                    .cmd(admin::failedreminders)
             }),
     );
 
@@ -136,6 +268,16 @@ fn main() {
     if let Err(why) = client.start() {
         error!("Client error: {:?}", why);
     }
+
+    let mut pool = ConnectionPool::new();
+    match aggregator.flush(&mut pool) {
+        Ok(flushed) => flushed.reward(&mut pool, &config),
+        Err(why) => error!("Failed to update statistics on shutdown: {}", why),
+    }
+    // Whoever is still in voice gets a new session when the bot is back.
+    if let Err(why) = pool.end_voice_sessions(Utc::now().naive_utc()) {
+        error!("Failed to end voice sessions on shutdown: {}", why);
+    }
 }
 
 command!(about(_ctx, msg, _args) {
@@ -146,16 +288,61 @@ command!(about(_ctx, msg, _args) {
     }
 });
 
+/// The lines of `!help`, sent in as many messages as they need.
+const HELP: &[&str] = &[
+    "Usage:",
+    "`!ping`: Bot responds with \"Pong!\".",
+    "`!help`: Print this message.",
+    "`!role`: Join or leave a public role. `!rank` no longer does this, it shows your level now.",
+    "`!roles`: Print a list of roles you can join.",
+    "`!stats x #channel`: List the 10 most active users for the last `x` days (defaults to 7), \
+     optionally only in one channel. Add `by messages`, `by words` or `by wpm` to sort \
+     differently, `top n` to list `n` users a page and `page n` to start on another page. \
+     React with the arrows to turn the page. Mention someone to see their daily activity, \
+     lifetime totals and streak of days in a row instead.",
+    "`!stats chart x`: Draw the messages per day, optionally of someone you mention.",
+    "`!stats heatmap x timezone`: Draw when the server is active, by weekday and hour.",
+    "`!stats export x csv|json`: Upload the messages and words per member and day as a file.",
+    "`!stats voice x`: List the 10 members who spent the most time in voice the last `x` days.",
+    "`!stats members x`: Show how many joined and left the last `x` days, and how many of \
+     the new members posted in their first week.",
+    "`!channelstats x`: List the 10 most active channels for the last `x` days.",
+    "`!emojistats x`: List the most and least used emoji of the server the last `x` days, \
+     and the ones nobody used.",
+    "`!rank @user`: Show your level, XP and rank, or those of someone you mention.",
+    "`!levels page`: List the members with the most XP. Messages earn XP, with a cooldown against spam.",
+    "`!levelroles`: List the roles given for reaching levels. Admins can `!levelroles add level @role`, \
+     `!levelroles remove level` and `!levelroles announce #channel|off`.",
+    "`!milestones #channel|off`: Congratulate members in a channel on their 1000th and 10000th \
+     message and 100 days in a row, once each.",
+    "`!remindme time message`: Makes the bot send you a PM containing the message at the \
+     given time, e.g. `2h30m`, `tomorrow 9am`, `next friday` or `2026-11-02 18:00`. \
+     Start with `every` to repeat it, e.g. `every monday 18:00` or `every 2 weeks`. \
+     Start with `here` to be reminded in the channel instead of by PM, \
+     or `join` to let others sign up by reacting.",
+    "`!remind @user or @role time message`: Same as `!remindme`, for other people. \
+     Roles must be mentionable, unless you may mention every role.",
+    "`!reminders`: List your pending reminders, which you can `!remindme cancel id` \
+     or `!remindme edit id time or message`. `!reminders failed` lists the ones \
+     that couldn't be delivered.",
+    "`!timezone name`: Sets your timezone, e.g. `Europe/Oslo`, for reminders and dates.",
+];
+
 command!(help(_ctx, msg, _args) {
-    const HELP: &str = "Usage:\
-        `!ping`: Bot responds with \"Pong!\".\
-        `!help`: Print this message.\
-        `!role`: Join or leave a public role.\
-        `!roles`: Print a list of roles you can join.\
-        `!stats x`: List the 5 most active users for the last `x` days (defaults to 7).\
-        `!remindme x scale message`: Makes the bot send you a PM containing the message after \
-        x minutes/hours/days/weeks.";
-    if let Err(why) = msg.channel_id.say(HELP) {
-        println!("Error sending message: {:?}", why);
-    }
+    let lines: Vec<String> = HELP.iter().map(|line| line.to_string()).collect();
+    util::say_in_parts(&msg.channel_id, &lines);
 });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_fits_in_messages() {
+        let lines: Vec<String> = HELP.iter().map(|line| line.to_string()).collect();
+        let messages = util::join_lines(&lines, util::MAX_MESSAGE_LENGTH);
+        for message in messages {
+            assert!(message.chars().count() <= util::MAX_MESSAGE_LENGTH, "{}", message);
+        }
+    }
+}
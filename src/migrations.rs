@@ -0,0 +1,70 @@
+use postgres::Connection;
+
+use command_error::CommandError;
+
+/// Database migrations in the order they're applied, by version.
+/// Applied migrations must never be changed; add a new one instead.
+static MIGRATIONS: &[(i32, &str)] = &[
+    (1, include_str!("../migrations/0001_initial.sql")),
+    (2, include_str!("../migrations/0002_reminder_bookmarks.sql")),
+    (3, include_str!("../migrations/0003_reminder_delivery.sql")),
+    (4, include_str!("../migrations/0004_timezones.sql")),
+    (5, include_str!("../migrations/0005_statistics_channels.sql")),
+    (6, include_str!("../migrations/0006_hourly_statistics.sql")),
+    (7, include_str!("../migrations/0007_usernames.sql")),
+    (8, include_str!("../migrations/0008_monthly_statistics.sql")),
+    (9, include_str!("../migrations/0009_voice.sql")),
+    (10, include_str!("../migrations/0010_emoji_statistics.sql")),
+    (11, include_str!("../migrations/0011_member_events.sql")),
+    (12, include_str!("../migrations/0012_levels.sql")),
+    (13, include_str!("../migrations/0013_streaks.sql")),
+    (14, include_str!("../migrations/0014_voice_heartbeat.sql")),
+    (15, include_str!("../migrations/0015_member_totals.sql")),
+    (16, include_str!("../migrations/0016_member_first_posts.sql")),
+    (17, include_str!("../migrations/0017_failed_occurrences.sql")),
+    (18, include_str!("../migrations/0018_long_reminder_messages.sql")),
+];
+
+/// Applies every migration that hasn't been applied to the database yet,
+/// each in its own transaction.
+pub fn run(conn: &Connection) -> Result<(), CommandError> {
+    conn.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+        )",
+    )?;
+
+    for &(version, sql) in MIGRATIONS {
+        let transaction = conn.transaction()?;
+        // Keeps two instances starting at once from applying the same migration.
+        transaction.batch_execute("LOCK TABLE schema_migrations IN EXCLUSIVE MODE")?;
+        let applied = !transaction
+            .query("SELECT 1 FROM schema_migrations WHERE version = $1", &[&version])?
+            .is_empty();
+        if applied {
+            continue;
+        }
+
+        info!("Applying database migration {}", version);
+        transaction
+            .batch_execute(sql)
+            .map_err(|why| format!("Migration {} failed: {}", version, why))?;
+        transaction.execute("INSERT INTO schema_migrations (version) VALUES ($1)", &[&version])?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_sequential() {
+        for (i, &(version, _)) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(version, i as i32 + 1);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use chrono::NaiveDate;
+use chrono::offset::Utc;
+use serenity::model::id::{GuildId, UserId};
+use serenity::model::misc::Mentionable;
+
+use command_error::CommandError;
+use connectionpool::{ConnectionPool, LifetimeStatistics};
+
+/// Something worth congratulating a member on, once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Milestone {
+    /// Messages posted since they were first counted.
+    Messages(i64),
+    /// Days in a row they posted on.
+    Streak(i64),
+}
+
+const MILESTONES: &[Milestone] = &[
+    Milestone::Messages(1000),
+    Milestone::Messages(10000),
+    Milestone::Streak(100),
+];
+
+impl Milestone {
+    /// How the milestone is stored, to know it was reached.
+    fn key(&self) -> String {
+        match *self {
+            Milestone::Messages(messages) => format!("messages-{}", messages),
+            Milestone::Streak(days) => format!("streak-{}", days),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match *self {
+            Milestone::Messages(messages) => format!("you've posted {} messages", messages),
+            Milestone::Streak(days) => format!("you've posted {} days in a row", days),
+        }
+    }
+}
+
+/// The milestones a member has reached with their activity so far.
+fn reached_milestones(statistics: &LifetimeStatistics) -> Vec<Milestone> {
+    MILESTONES
+        .iter()
+        .cloned()
+        .filter(|m| match *m {
+            Milestone::Messages(messages) => statistics.messages >= messages,
+            Milestone::Streak(days) => statistics.streak >= days,
+        })
+        .collect()
+}
+
+/// Records the milestones members who just posted have reached, and announces
+/// the new ones if the guild wants milestones announced.
+pub fn announce_milestones(pool: &mut ConnectionPool, members: &[(GuildId, UserId)]) {
+    let today = Utc::now().naive_utc().date();
+    for &(guild_id, user_id) in members {
+        if let Err(why) = announce_new_milestones(pool, guild_id, user_id, today) {
+            error!("Failed to announce milestones: {}", why);
+        }
+    }
+}
+
+fn announce_new_milestones(
+    pool: &mut ConnectionPool,
+    guild_id: GuildId,
+    user_id: UserId,
+    today: NaiveDate,
+) -> Result<(), CommandError> {
+    let statistics = pool.get_lifetime_statistics(guild_id, user_id, today)?;
+    let mut new = vec![];
+    for milestone in reached_milestones(&statistics) {
+        if pool.add_milestone(guild_id, user_id, &milestone.key())? {
+            new.push(milestone);
+        }
+    }
+    if new.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(channel_id) = pool.get_milestone_announcement_channel(guild_id)? {
+        for milestone in new {
+            channel_id.say(&format!("Congratulations {}, {}!", user_id.mention(), milestone.describe()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn milestones_are_reached_at_thresholds() {
+        let statistics = LifetimeStatistics { messages: 999, words: 0, streak: 100, longest_streak: 120 };
+        assert_eq!(reached_milestones(&statistics), vec![Milestone::Streak(100)]);
+
+        let statistics = LifetimeStatistics { messages: 10000, words: 0, streak: 99, longest_streak: 120 };
+        assert_eq!(
+            reached_milestones(&statistics),
+            vec![Milestone::Messages(1000), Milestone::Messages(10000)]
+        );
+        assert_eq!(Milestone::Messages(1000).key(), "messages-1000");
+        assert_eq!(Milestone::Streak(100).key(), "streak-100");
+    }
+}
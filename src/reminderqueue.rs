@@ -0,0 +1,135 @@
+use chrono::NaiveDateTime;
+use chrono::offset::Utc;
+use std::cmp::{self, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use typemap::Key;
+
+use command_error::CommandError;
+
+/// In-memory queue of the times at which reminders fall due, in UTC.
+///
+/// The queue only decides when the reminder watcher wakes up. The database
+/// stays the source of truth for what is due, so times left behind by
+/// cancelled or edited reminders merely cause a wasted check.
+#[derive(Clone)]
+pub struct ReminderQueue {
+    inner: Arc<(Mutex<BinaryHeap<Reverse<NaiveDateTime>>>, Condvar)>,
+}
+
+impl ReminderQueue {
+    pub fn new() -> ReminderQueue {
+        ReminderQueue {
+            inner: Arc::new((Mutex::new(BinaryHeap::new()), Condvar::new())),
+        }
+    }
+
+    /// Queues a due time, waking the watcher if it's earlier than the time
+    /// it's currently waiting for.
+    pub fn push(&self, due: NaiveDateTime) {
+        let (ref lock, ref condvar) = *self.inner;
+        let mut queue = lock.lock().unwrap();
+        let earliest = queue.peek().map_or(true, |&Reverse(next)| due < next);
+        queue.push(Reverse(due));
+        if earliest {
+            condvar.notify_all();
+        }
+    }
+
+    /// Replaces the queue with the due times returned by `load`.
+    /// The queue is locked while loading, so no pushes are lost in between.
+    pub fn resync<F>(&self, load: F) -> Result<(), CommandError>
+    where
+        F: FnOnce() -> Result<Vec<NaiveDateTime>, CommandError>,
+    {
+        let (ref lock, ref condvar) = *self.inner;
+        let mut queue = lock.lock().unwrap();
+        *queue = load()?.into_iter().map(Reverse).collect();
+        condvar.notify_all();
+        Ok(())
+    }
+
+    /// Blocks until a queued time has passed, or at most `max_wait`.
+    /// Removes all passed times and returns whether there were any.
+    pub fn wait_until_due(&self, max_wait: Duration) -> bool {
+        let (ref lock, ref condvar) = *self.inner;
+        let deadline = Instant::now() + max_wait;
+        let mut queue = lock.lock().unwrap();
+        loop {
+            let now = Utc::now().naive_utc();
+            let mut due = false;
+            while queue.peek().map_or(false, |&Reverse(next)| next <= now) {
+                queue.pop();
+                due = true;
+            }
+            if due {
+                return true;
+            }
+
+            let instant = Instant::now();
+            if instant >= deadline {
+                return false;
+            }
+            let mut wait = deadline - instant;
+            if let Some(&Reverse(next)) = queue.peek() {
+                // `next` is in the future, so the conversion can't fail.
+                let until = next.signed_duration_since(now).to_std().unwrap_or(wait);
+                wait = cmp::min(wait, until);
+            }
+
+            queue = condvar.wait_timeout(queue, wait).unwrap().0;
+        }
+    }
+}
+
+impl Default for ReminderQueue {
+    fn default() -> Self {
+        ReminderQueue::new()
+    }
+}
+
+impl Key for ReminderQueue {
+    type Value = ReminderQueue;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as OldDuration;
+    use std::thread;
+
+    #[test]
+    fn passed_times_are_due() {
+        let queue = ReminderQueue::new();
+        queue.push(Utc::now().naive_utc() - OldDuration::seconds(5));
+        queue.push(Utc::now().naive_utc() + OldDuration::hours(1));
+        assert!(queue.wait_until_due(Duration::from_secs(5)));
+        assert!(!queue.wait_until_due(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn pushing_an_earlier_time_wakes_the_waiter() {
+        let queue = ReminderQueue::new();
+        queue.push(Utc::now().naive_utc() + OldDuration::hours(1));
+
+        let pusher = queue.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            pusher.push(Utc::now().naive_utc() + OldDuration::milliseconds(50));
+        });
+
+        let started = Instant::now();
+        assert!(queue.wait_until_due(Duration::from_secs(10)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn resync_replaces_the_queue() {
+        let queue = ReminderQueue::new();
+        queue.push(Utc::now().naive_utc() - OldDuration::seconds(5));
+        queue.resync(|| Ok(vec![])).unwrap();
+        assert!(!queue.wait_until_due(Duration::from_millis(10)));
+    }
+}
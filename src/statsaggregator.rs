@@ -0,0 +1,221 @@
+use chrono::{Duration, NaiveDateTime};
+use chrono::offset::Utc;
+use serenity::model::id::{GuildId, UserId};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time;
+use typemap::Key;
+
+use command_error::CommandError;
+use connectionpool::{
+    ConnectionPool, EmojiDelta, EmojiKey, PendingStatistics, StatisticsDelta, StatisticsKey, XpChange,
+};
+use levels::{self, LevelConfig};
+use milestones;
+
+/// How often counted messages are written to the database, in seconds.
+const FLUSH_INTERVAL_SECS: u64 = 30;
+
+/// Message and word counts per guild, channel, user and hour, and the use of
+/// emoji, that haven't been written to the database yet. Counting is done in
+/// memory so that handling a message never waits for the database.
+#[derive(Clone)]
+pub struct StatsAggregator {
+    pending: Arc<Mutex<PendingStatistics>>,
+    /// When members can earn XP again.
+    xp_cooldowns: Arc<Mutex<HashMap<(GuildId, UserId), NaiveDateTime>>>,
+}
+
+impl StatsAggregator {
+    pub fn new() -> StatsAggregator {
+        StatsAggregator {
+            pending: Arc::new(Mutex::new(PendingStatistics::default())),
+            xp_cooldowns: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Counts a message with `words` words.
+    pub fn record(&self, key: StatisticsKey, words: i32) {
+        let mut pending = self.pending.lock().unwrap();
+        let delta = pending.counts.entry(key).or_insert_with(StatisticsDelta::default);
+        delta.messages += 1;
+        delta.words += words;
+    }
+
+    /// Remembers the username of a user, so they can still be named after
+    /// leaving the guild.
+    pub fn record_username(&self, user_id: UserId, username: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.usernames.get(&user_id).map(|n| n.as_str()) != Some(username) {
+            pending.usernames.insert(user_id, username.to_owned());
+        }
+    }
+
+    /// Counts the use of an emoji in a message or as a reaction.
+    pub fn record_emoji(&self, key: EmojiKey, delta: EmojiDelta) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.emoji.entry(key).or_insert_with(EmojiDelta::default).add(&delta);
+    }
+
+    /// Gives a member XP for a message sent at `at`, unless they got some
+    /// less than `cooldown` ago.
+    pub fn record_xp(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        xp: i64,
+        at: NaiveDateTime,
+        cooldown: Duration,
+    ) {
+        {
+            let mut cooldowns = self.xp_cooldowns.lock().unwrap();
+            let until = cooldowns.entry((guild_id, user_id)).or_insert(at);
+            if *until > at {
+                return;
+            }
+            *until = at + cooldown;
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        *pending.xp.entry((guild_id, user_id)).or_insert(0) += xp;
+    }
+
+    /// Writes everything counted so far to the database in one transaction,
+    /// and returns who posted and how the XP of members changed.
+    /// If that fails, the counts are kept for the next flush.
+    pub fn flush(&self, pool: &mut ConnectionPool) -> Result<Flushed, CommandError> {
+        // Cooldowns that are over don't need to be kept.
+        let now = Utc::now().naive_utc();
+        self.xp_cooldowns.lock().unwrap().retain(|_, until| *until > now);
+
+        let pending = self.take();
+        if pending.is_empty() {
+            return Ok(Flushed::default());
+        }
+
+        match pool.update_statistics(&pending) {
+            Ok(xp_changes) => {
+                let members: HashSet<(GuildId, UserId)> =
+                    pending.counts.keys().map(|k| (k.guild_id, k.user_id)).collect();
+                Ok(Flushed {
+                    members: members.into_iter().collect(),
+                    xp_changes,
+                })
+            }
+            Err(why) => {
+                self.restore(pending);
+                Err(why)
+            }
+        }
+    }
+
+    fn take(&self) -> PendingStatistics {
+        mem::replace(&mut *self.pending.lock().unwrap(), PendingStatistics::default())
+    }
+
+    /// Puts back counts that couldn't be written, adding any counted since.
+    fn restore(&self, old: PendingStatistics) {
+        self.pending.lock().unwrap().merge(old);
+    }
+}
+
+/// What was written by a flush.
+#[derive(Debug, Default)]
+pub struct Flushed {
+    /// The members who posted since the last flush.
+    pub members: Vec<(GuildId, UserId)>,
+    pub xp_changes: Vec<XpChange>,
+}
+
+impl Flushed {
+    /// Rewards members who went up a level and announces the milestones
+    /// they reached.
+    pub fn reward(&self, pool: &mut ConnectionPool, config: &LevelConfig) {
+        levels::reward_level_ups(pool, config, &self.xp_changes);
+        milestones::announce_milestones(pool, &self.members);
+    }
+}
+
+impl Default for StatsAggregator {
+    fn default() -> Self {
+        StatsAggregator::new()
+    }
+}
+
+impl Key for StatsAggregator {
+    type Value = StatsAggregator;
+}
+
+/// Infinite loop that writes counted messages to the database every
+/// `FLUSH_INTERVAL_SECS` seconds, and rewards members who went up a level or
+/// reached a milestone.
+pub fn flush_periodically(mut pool: ConnectionPool, aggregator: StatsAggregator, config: LevelConfig) -> ! {
+    loop {
+        thread::sleep(time::Duration::from_secs(FLUSH_INTERVAL_SECS));
+        match aggregator.flush(&mut pool) {
+            Ok(flushed) => flushed.reward(&mut pool, &config),
+            Err(why) => error!("Failed to update statistics: {}", why),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use serenity::model::id::ChannelId;
+
+    fn key(user_id: u64) -> StatisticsKey {
+        StatisticsKey {
+            guild_id: GuildId(1),
+            channel_id: ChannelId(2),
+            user_id: UserId(user_id),
+            hour: NaiveDate::from_ymd(2026, 10, 14).and_hms(12, 0, 0),
+        }
+    }
+
+    #[test]
+    fn counts_are_summed_per_key() {
+        let aggregator = StatsAggregator::new();
+        aggregator.record(key(3), 3);
+        aggregator.record(key(3), 4);
+        aggregator.record(key(4), 1);
+
+        let pending = aggregator.take();
+        assert_eq!(pending.counts.len(), 2);
+        assert_eq!(pending.counts[&key(3)], StatisticsDelta { messages: 2, words: 7 });
+        assert!(aggregator.take().is_empty());
+    }
+
+    #[test]
+    fn restored_counts_are_merged() {
+        let aggregator = StatsAggregator::new();
+        aggregator.record(key(3), 3);
+        aggregator.record_username(UserId(3), "old");
+        let pending = aggregator.take();
+
+        aggregator.record(key(3), 2);
+        aggregator.record_username(UserId(3), "new");
+        aggregator.restore(pending);
+        let pending = aggregator.take();
+        assert_eq!(pending.counts[&key(3)], StatisticsDelta { messages: 2, words: 5 });
+        assert_eq!(pending.usernames[&UserId(3)], "new");
+    }
+
+    #[test]
+    fn xp_has_a_cooldown() {
+        let aggregator = StatsAggregator::new();
+        let at = NaiveDate::from_ymd(2026, 10, 14).and_hms(12, 0, 0);
+        let cooldown = Duration::seconds(60);
+        aggregator.record_xp(GuildId(1), UserId(3), 20, at, cooldown);
+        aggregator.record_xp(GuildId(1), UserId(3), 20, at + Duration::seconds(59), cooldown);
+        aggregator.record_xp(GuildId(1), UserId(3), 20, at + Duration::seconds(60), cooldown);
+        aggregator.record_xp(GuildId(2), UserId(3), 20, at, cooldown);
+
+        let pending = aggregator.take();
+        assert_eq!(pending.xp[&(GuildId(1), UserId(3))], 40);
+        assert_eq!(pending.xp[&(GuildId(2), UserId(3))], 20);
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use std::env;
+use std::thread;
+use std::time;
+
+use connectionpool::ConnectionPool;
+
+/// How often old statistics are rolled up and pruned, in seconds.
+const MAINTENANCE_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+/// How many days of daily statistics are kept by default.
+const DEFAULT_DAYS: i64 = 90;
+
+/// How many days of hourly statistics are kept by default, which is as far
+/// back as `?stats heatmap` can look.
+const DEFAULT_HOURLY_DAYS: i64 = 365;
+
+/// How long statistics are kept before they're rolled up or deleted, set by
+/// `STATISTICS_RETENTION_DAYS` and `HOURLY_STATISTICS_RETENTION_DAYS`.
+#[derive(Debug, Clone, Copy)]
+pub struct Retention {
+    /// Days of daily statistics to keep. Older ones are summed up per month,
+    /// and older voice sessions are deleted.
+    pub days: i64,
+    /// Days of hourly statistics to keep. Older ones are deleted.
+    pub hourly_days: i64,
+}
+
+impl Retention {
+    pub fn from_env() -> Retention {
+        Retention {
+            days: days_from_env("STATISTICS_RETENTION_DAYS", DEFAULT_DAYS),
+            hourly_days: days_from_env("HOURLY_STATISTICS_RETENTION_DAYS", DEFAULT_HOURLY_DAYS),
+        }
+    }
+}
+
+fn days_from_env(name: &str, default: i64) -> i64 {
+    match env::var(name) {
+        Ok(days) => match days.parse() {
+            Ok(days) if days > 0 => days,
+            _ => panic!("Expected {} to be a positive number of days", name),
+        },
+        Err(_) => default,
+    }
+}
+
+/// Infinite loop that rolls up and prunes old statistics every
+/// `MAINTENANCE_INTERVAL_SECS` seconds, starting right away.
+pub fn maintain_periodically(mut pool: ConnectionPool, retention: Retention) -> ! {
+    loop {
+        let today = Utc::today().naive_utc();
+
+        match pool.roll_up_statistics(roll_up_before(today, retention.days)) {
+            Ok(0) => {}
+            Ok(rows) => info!("Rolled up old statistics into {} monthly rows", rows),
+            Err(why) => error!("Failed to roll up statistics: {}", why),
+        }
+
+        let before = (today - Duration::days(retention.hourly_days)).and_hms(0, 0, 0);
+        match pool.prune_hourly_statistics(before) {
+            Ok(0) => {}
+            Ok(rows) => info!("Deleted {} rows of old hourly statistics", rows),
+            Err(why) => error!("Failed to prune hourly statistics: {}", why),
+        }
+
+        let before = (today - Duration::days(retention.days)).and_hms(0, 0, 0);
+        match pool.prune_voice_sessions(before) {
+            Ok(0) => {}
+            Ok(rows) => info!("Deleted {} old voice sessions", rows),
+            Err(why) => error!("Failed to prune voice sessions: {}", why),
+        }
+
+        thread::sleep(time::Duration::from_secs(MAINTENANCE_INTERVAL_SECS));
+    }
+}
+
+/// The first day that is kept as daily statistics when keeping `days` days.
+/// Only whole months are rolled up, so it's the first day of a month.
+fn roll_up_before(today: NaiveDate, days: i64) -> NaiveDate {
+    (today - Duration::days(days)).with_day(1).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_whole_months_are_rolled_up() {
+        let today = NaiveDate::from_ymd(2026, 10, 17);
+        assert_eq!(roll_up_before(today, 90), NaiveDate::from_ymd(2026, 7, 1));
+        assert_eq!(roll_up_before(today, 16), NaiveDate::from_ymd(2026, 10, 1));
+        assert_eq!(roll_up_before(today, 17), NaiveDate::from_ymd(2026, 9, 1));
+    }
+}
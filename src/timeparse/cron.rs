@@ -0,0 +1,263 @@
+//! Minimal five-field cron schedules (`minute hour day-of-month month day-of-week`).
+//!
+//! Each field accepts `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps
+//! (`*/15`, `0-30/10`). Months and weekdays may also be given by their
+//! three-letter English names.
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Timelike};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// How many days ahead to look for a matching time before giving up.
+const SEARCH_DAYS: i64 = 366 * 5;
+
+static MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+static WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+static WEEKDAY_NAMES: [&str; 7] = [
+    "sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether day of month and day of week were both restricted, in which
+    /// case a day matching either of them is accepted.
+    either_day: bool,
+    source: String,
+}
+
+impl Schedule {
+    /// Finds the first matching time strictly after `after`, in its timezone.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let local = after.naive_local();
+        let start_time = NaiveTime::from_hms(local.hour(), local.minute(), 0);
+
+        let mut date = local.date();
+        for _ in 0..SEARCH_DAYS {
+            if self.matches_day(date.day(), date.month(), date.weekday().num_days_from_sunday()) {
+                for hour in (0..24).filter(|h| bit(self.hours, *h)) {
+                    for minute in (0..60).filter(|m| bit(self.minutes, *m)) {
+                        let time = NaiveTime::from_hms(hour, minute, 0);
+                        if date == local.date() && time <= start_time {
+                            continue;
+                        }
+                        // Times skipped by daylight saving are passed over.
+                        if let Some(found) = timezone.from_local_datetime(&date.and_time(time)).earliest() {
+                            return Some(found);
+                        }
+                    }
+                }
+            }
+            date = date.checked_add_signed(Duration::days(1))?;
+        }
+
+        None
+    }
+
+    /// Describes the schedule for people, like `every monday and friday at
+    /// 18:00`. Anything but one time of day on some weekdays is shown as the
+    /// cron expression.
+    pub fn describe(&self) -> String {
+        let fields: Vec<&str> = self.source.split(' ').collect();
+        let time = match (fields[0].parse::<u32>(), fields[1].parse::<u32>()) {
+            (Ok(minute), Ok(hour)) if fields[2] == "*" && fields[3] == "*" => {
+                format!("{:02}:{:02}", hour, minute)
+            }
+            _ => return format!("every {}", self.source),
+        };
+
+        // Weeks start on monday here.
+        let days: Vec<&str> = [1, 2, 3, 4, 5, 6, 0]
+            .iter()
+            .filter(|&&d| bit(self.weekdays, d))
+            .map(|&d| WEEKDAY_NAMES[d as usize])
+            .collect();
+        let days = match days.len() {
+            7 => "day".to_owned(),
+            5 if !bit(self.weekdays, 0) && !bit(self.weekdays, 6) => "weekday".to_owned(),
+            1 => days[0].to_owned(),
+            n => format!("{} and {}", days[..n - 1].join(", "), days[n - 1]),
+        };
+        format!("every {} at {}", days, time)
+    }
+
+    fn matches_day(&self, day: u32, month: u32, weekday: u32) -> bool {
+        if !bit(self.months, month) {
+            return false;
+        }
+        let day_matches = bit(self.days, day);
+        let weekday_matches = bit(self.weekdays, weekday);
+        if self.either_day {
+            day_matches || weekday_matches
+        } else {
+            day_matches && weekday_matches
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<String> = s.split_whitespace().map(|f| f.to_lowercase()).collect();
+        if fields.len() != 5 {
+            return Err(format!("A cron expression needs 5 fields, got {}.", fields.len()));
+        }
+
+        let mut weekdays = parse_field(&fields[4], 0, 7, &WEEKDAYS)?;
+        // Both 0 and 7 mean sunday.
+        if bit(weekdays, 7) {
+            weekdays |= 1;
+        }
+
+        Ok(Schedule {
+            minutes: parse_field(&fields[0], 0, 59, &[])?,
+            hours: parse_field(&fields[1], 0, 23, &[])?,
+            days: parse_field(&fields[2], 1, 31, &[])?,
+            months: parse_field(&fields[3], 1, 12, &MONTHS)?,
+            weekdays,
+            either_day: !fields[2].starts_with('*') && !fields[4].starts_with('*'),
+            source: fields.join(" "),
+        })
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn bit(set: u64, n: u32) -> bool {
+    set & (1 << n) != 0
+}
+
+/// Parses one field into a bit set of the values it matches.
+/// `names` are alternative spellings for the values starting at `min`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let n = match names.iter().position(|name| *name == s) {
+            Some(i) => i as u32 + min,
+            None => s.parse::<u32>().map_err(|_| format!("`{}` is not a valid cron value.", s))?,
+        };
+        if n < min || n > max {
+            return Err(format!("`{}` is out of range ({}-{}).", s, min, max));
+        }
+        Ok(n)
+    };
+
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.find('/') {
+            Some(i) => {
+                let step = part[i + 1..]
+                    .parse::<u32>()
+                    .map_err(|_| format!("`{}` is not a valid cron step.", part))?;
+                (&part[..i], step)
+            }
+            None => (part, 1),
+        };
+        if step == 0 || step > max {
+            return Err(format!("`{}` is not a valid cron step.", part));
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some(i) = range.find('-') {
+            (value(&range[..i])?, value(&range[i + 1..])?)
+        } else {
+            let start = value(range)?;
+            // `5/10` means from 5 to the end in steps of 10.
+            (start, if step > 1 { max } else { start })
+        };
+        if start > end {
+            return Err(format!("`{}` is not a valid cron range.", range));
+        }
+
+        let mut n = start;
+        while n <= end {
+            set |= 1 << n;
+            n += step;
+        }
+    }
+
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Utc};
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&NaiveDate::from_ymd(y, m, d).and_hms(h, min, 0))
+    }
+
+    fn next(expr: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        expr.parse::<Schedule>().unwrap().next_after(&after)
+    }
+
+    #[test]
+    fn weekly() {
+        // 2026-10-14 is a wednesday.
+        let now = utc(2026, 10, 14, 12, 0);
+        assert_eq!(next("0 18 * * mon", now), Some(utc(2026, 10, 19, 18, 0)));
+        assert_eq!(next("0 18 * * 3", now), Some(utc(2026, 10, 14, 18, 0)));
+        assert_eq!(next("0 9 * * 3", now), Some(utc(2026, 10, 21, 9, 0)));
+        assert_eq!(next("30 8 * * 7", now), Some(utc(2026, 10, 18, 8, 30)));
+    }
+
+    #[test]
+    fn steps_and_lists() {
+        let now = utc(2026, 10, 14, 12, 7);
+        assert_eq!(next("*/15 * * * *", now), Some(utc(2026, 10, 14, 12, 15)));
+        assert_eq!(next("0 8,20 * * *", now), Some(utc(2026, 10, 14, 20, 0)));
+        assert_eq!(next("0 0 1 jan-mar *", now), Some(utc(2027, 1, 1, 0, 0)));
+        assert_eq!(next("0 0 5/10 * *", now), Some(utc(2026, 10, 15, 0, 0)));
+    }
+
+    #[test]
+    fn day_of_month_or_weekday() {
+        // Either the 1st or a friday, whichever comes first.
+        let now = utc(2026, 10, 14, 12, 0);
+        assert_eq!(next("0 12 1 * fri", now), Some(utc(2026, 10, 16, 12, 0)));
+        assert_eq!(next("0 12 30 2 *", now), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("* * * *".parse::<Schedule>().is_err());
+        assert!("60 * * * *".parse::<Schedule>().is_err());
+        assert!("* * 0 * *".parse::<Schedule>().is_err());
+        assert!("*/0 * * * *".parse::<Schedule>().is_err());
+        assert!("5/4294967295 * * * *".parse::<Schedule>().is_err());
+        assert!("*/60 * * * *".parse::<Schedule>().is_err());
+        assert!("5-1 * * * *".parse::<Schedule>().is_err());
+        assert!("* * * * funday".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn described_for_people() {
+        let describe = |expr: &str| expr.parse::<Schedule>().unwrap().describe();
+        assert_eq!(describe("0 9 * * *"), "every day at 09:00");
+        assert_eq!(describe("30 18 * * 1,2,3,4,5"), "every weekday at 18:30");
+        assert_eq!(describe("0 18 * * mon"), "every monday at 18:00");
+        assert_eq!(describe("0 18 * * 0,3,5"), "every wednesday, friday and sunday at 18:00");
+        assert_eq!(describe("*/15 * * * *"), "every */15 * * * *");
+        assert_eq!(describe("0 0 1 jan-mar *"), "every 0 0 1 jan-mar *");
+    }
+
+    #[test]
+    fn round_trip() {
+        let schedule = "0 18 * * MON".parse::<Schedule>().unwrap();
+        assert_eq!(schedule.to_string(), "0 18 * * mon");
+        assert_eq!(schedule.to_string().parse::<Schedule>(), Ok(schedule));
+    }
+}
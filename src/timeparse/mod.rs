@@ -0,0 +1,698 @@
+//! Parser for the time expressions accepted by `?remindme`.
+//!
+//! Understands compound durations (`2h30m`, `1 day 4 hours`, `in an hour`),
+//! absolute dates and times (`2026-11-02 18:00`, `tomorrow 9am`, `at noon`)
+//! and weekday names (`friday`, `next fri 18:30`), as well as recurrences
+//! (`every monday 18:00`, `every 2 weeks`, `every 0 18 * * 1`).
+
+pub mod cron;
+
+use chrono::{Datelike, DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday};
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use self::cron::Schedule;
+
+/// Largest number of seconds a `chrono::Duration` can hold without panicking.
+const MAX_SECONDS: i64 = i64::MAX / 1000;
+
+/// A point in time as written by the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeExpr {
+    /// An offset from the current time, e.g. `2h30m`.
+    Relative(Duration),
+    /// A wall clock date and time, e.g. `tomorrow 9am`.
+    Absolute(NaiveDateTime),
+}
+
+impl TimeExpr {
+    /// Turns the expression into a point in time, relative to `now`.
+    /// Absolute times are interpreted in the timezone of `now`.
+    /// Returns `None` if the result does not exist or can't be represented.
+    pub fn resolve<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        match *self {
+            TimeExpr::Relative(duration) => now.clone().checked_add_signed(duration),
+            TimeExpr::Absolute(date) => now.timezone().from_local_datetime(&date).earliest(),
+        }
+    }
+}
+
+/// How a recurring reminder repeats.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recurrence {
+    /// A fixed interval after the previous occurrence, e.g. `every 2 weeks`.
+    Interval(Duration),
+    /// A cron schedule, e.g. `every 0 18 * * 1`. Weekday expressions such as
+    /// `every monday 18:00` are turned into these as well.
+    Cron(Schedule),
+}
+
+impl Recurrence {
+    /// Finds the first occurrence strictly after `after`, in its timezone.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        match *self {
+            Recurrence::Interval(duration) => after.clone().checked_add_signed(duration),
+            Recurrence::Cron(ref schedule) => schedule.next_after(after),
+        }
+    }
+
+    /// Describes the recurrence for people, like `every 2 weeks` or `every
+    /// day at 09:00`, unlike `Display` which is meant to be stored.
+    pub fn describe(&self) -> String {
+        match *self {
+            Recurrence::Interval(duration) => {
+                const UNITS: [(i64, &str); 5] = [
+                    (7 * 24 * 60 * 60, "week"),
+                    (24 * 60 * 60, "day"),
+                    (60 * 60, "hour"),
+                    (60, "minute"),
+                    (1, "second"),
+                ];
+                let seconds = duration.num_seconds();
+                let &(size, unit) = UNITS
+                    .iter()
+                    .find(|&&(size, _)| seconds % size == 0)
+                    .unwrap_or(&UNITS[4]);
+                match seconds / size {
+                    1 => format!("every {}", unit),
+                    n => format!("every {} {}s", n, unit),
+                }
+            }
+            Recurrence::Cron(ref schedule) => schedule.describe(),
+        }
+    }
+}
+
+/// Formats the recurrence so that it can be read back with `parse_recurrence`.
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Recurrence::Interval(duration) => write!(f, "every {}s", duration.num_seconds()),
+            Recurrence::Cron(ref schedule) => write!(f, "every {}", schedule),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_recurrence(s)? {
+            Some((recurrence, "")) => Ok(recurrence),
+            _ => Err(ParseError::Unrecognised(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// Nothing was given to parse.
+    Empty,
+    /// The expression did not start with anything that looks like a time.
+    Unrecognised(String),
+    /// The duration is too large to be represented.
+    OutOfRange,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            ParseError::Empty => write!(f, "No time given."),
+            ParseError::Unrecognised(ref s) => write!(f, "Could not understand the time `{}`.", s),
+            ParseError::OutOfRange => write!(f, "That duration is too long."),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn description(&self) -> &str {
+        match *self {
+            ParseError::Empty => "no time given",
+            ParseError::Unrecognised(_) => "unrecognised time expression",
+            ParseError::OutOfRange => "duration out of range",
+        }
+    }
+}
+
+/// Parses a time expression from the start of `input`.
+///
+/// `now` is the user's local time, used to resolve expressions such as
+/// `tomorrow` or `friday`.
+/// On success the parsed expression is returned along with whatever
+/// remains of `input` after it, which is the reminder text.
+pub fn parse(input: &str, now: NaiveDateTime) -> Result<(TimeExpr, &str), ParseError> {
+    let tokens = tokenize(input);
+    if tokens.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let (expr, end) = if let Some((duration, end)) = parse_duration(&tokens, 0)? {
+        (TimeExpr::Relative(duration), end)
+    } else if let Some((date, end)) = parse_absolute(&tokens, 0, now) {
+        (TimeExpr::Absolute(date), end)
+    } else {
+        return Err(ParseError::Unrecognised(tokens[0].1.to_owned()));
+    };
+
+    let rest = match tokens.get(end) {
+        Some(&(offset, _)) => &input[offset..],
+        None => "",
+    };
+
+    Ok((expr, rest))
+}
+
+/// Parses a recurrence such as `every monday 18:00`, `every day at 9am`,
+/// `every 2 weeks` or the cron expression `every 0 18 * * 1` from the start
+/// of `input`, returning it along with the rest of `input`.
+///
+/// Returns `None` if `input` doesn't start with `every`.
+pub fn parse_recurrence(input: &str) -> Result<Option<(Recurrence, &str)>, ParseError> {
+    let tokens = tokenize(input);
+    if word(&tokens, 0).map_or(true, |w| w != "every") {
+        return Ok(None);
+    }
+
+    let (recurrence, end) = if let Some((schedule, end)) = parse_cron(&tokens, 1) {
+        (Recurrence::Cron(schedule), end)
+    } else if let Some((schedule, end)) = parse_weekly(&tokens, 1) {
+        (Recurrence::Cron(schedule), end)
+    } else if let Some((duration, end)) = parse_duration(&tokens, 1)? {
+        (Recurrence::Interval(duration), end)
+    } else if let Some(unit) = word(&tokens, 1).and_then(|w| unit_seconds(&w)) {
+        // `every hour`, `every week`.
+        (Recurrence::Interval(Duration::seconds(unit)), 2)
+    } else {
+        let what = tokens.get(1).map_or("every", |&(_, w)| w);
+        return Err(ParseError::Unrecognised(what.to_owned()));
+    };
+
+    if recurrence == Recurrence::Interval(Duration::zero()) {
+        return Err(ParseError::Unrecognised(tokens[1].1.to_owned()));
+    }
+
+    let rest = match tokens.get(end) {
+        Some(&(offset, _)) => &input[offset..],
+        None => "",
+    };
+
+    Ok(Some((recurrence, rest)))
+}
+
+/// Splits `input` on whitespace, keeping the byte offset of every word.
+fn tokenize(input: &str) -> Vec<(usize, &str)> {
+    let base = input.as_ptr() as usize;
+    input
+        .split_whitespace()
+        .map(|word| (word.as_ptr() as usize - base, word))
+        .collect()
+}
+
+/// Lowercased word at `index`, with trailing punctuation removed.
+fn word(tokens: &[(usize, &str)], index: usize) -> Option<String> {
+    tokens
+        .get(index)
+        .map(|&(_, w)| w.trim_end_matches(|c| c == ',' || c == '.').to_lowercase())
+}
+
+/// Parses durations such as `2h30m`, `1 day 4 hours` or `in an hour and 5 minutes`.
+fn parse_duration(
+    tokens: &[(usize, &str)],
+    start: usize,
+) -> Result<Option<(Duration, usize)>, ParseError> {
+    let mut i = start;
+    if word(tokens, i).map_or(false, |w| w == "in") {
+        i += 1;
+    }
+
+    let mut seconds: i64 = 0;
+    let mut found = false;
+    loop {
+        let mut next = i;
+        if found && word(tokens, next).map_or(false, |w| w == "and") {
+            next += 1;
+        }
+
+        let current = match word(tokens, next) {
+            Some(w) => w,
+            None => break,
+        };
+
+        let (amount, consumed) = if let Some(amount) = parse_compact(&current) {
+            (amount?, 1)
+        } else if let Some(unit) = word(tokens, next + 1).and_then(|w| unit_seconds(&w)) {
+            let count = match &*current {
+                "a" | "an" => 1,
+                n => match n.parse::<i64>() {
+                    Ok(n) if n >= 0 => n,
+                    _ => break,
+                },
+            };
+            (count.checked_mul(unit).ok_or(ParseError::OutOfRange)?, 2)
+        } else {
+            break;
+        };
+
+        seconds = seconds
+            .checked_add(amount)
+            .ok_or(ParseError::OutOfRange)?;
+        found = true;
+        i = next + consumed;
+    }
+
+    if !found {
+        return Ok(None);
+    }
+    if seconds > MAX_SECONDS {
+        return Err(ParseError::OutOfRange);
+    }
+
+    Ok(Some((Duration::seconds(seconds), i)))
+}
+
+/// Parses a single word made of number and unit pairs, like `2h30m` or `90min`.
+/// Returns `None` if the word isn't a duration at all.
+fn parse_compact(word: &str) -> Option<Result<i64, ParseError>> {
+    let mut total: i64 = 0;
+    let mut rest = word;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let (number, tail) = rest.split_at(digits);
+        let letters = tail.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(letters);
+
+        let unit = unit_seconds(unit)?;
+        let amount = number
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| n.checked_mul(unit))
+            .and_then(|n| n.checked_add(total));
+        total = match amount {
+            Some(n) => n,
+            None => return Some(Err(ParseError::OutOfRange)),
+        };
+        rest = tail;
+    }
+
+    Some(Ok(total))
+}
+
+/// Number of seconds in a duration unit.
+fn unit_seconds(unit: &str) -> Option<i64> {
+    match unit {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(60 * 60),
+        "d" | "day" | "days" => Some(24 * 60 * 60),
+        "w" | "wk" | "wks" | "week" | "weeks" => Some(7 * 24 * 60 * 60),
+        _ => None,
+    }
+}
+
+/// The date part of an absolute expression.
+enum DateSpec {
+    Date(NaiveDate),
+    /// The first matching weekday from today, or from tomorrow if `next` is set.
+    Weekday { day: Weekday, next: bool },
+}
+
+/// Parses absolute expressions such as `2026-11-02 18:00`, `tomorrow 9am`,
+/// `next friday` or `at 18:30`.
+fn parse_absolute(
+    tokens: &[(usize, &str)],
+    start: usize,
+    now: NaiveDateTime,
+) -> Option<(NaiveDateTime, usize)> {
+    let mut i = start;
+    if word(tokens, i).map_or(false, |w| w == "at" || w == "on") {
+        i += 1;
+    }
+
+    let today = now.date();
+    let date = match word(tokens, i) {
+        Some(ref w) if w == "today" => Some((DateSpec::Date(today), 1)),
+        Some(ref w) if w == "tomorrow" => Some((DateSpec::Date(today.succ()), 1)),
+        Some(ref w) if w == "next" => word(tokens, i + 1)
+            .and_then(|w| parse_weekday(&w))
+            .map(|day| (DateSpec::Weekday { day, next: true }, 2)),
+        Some(ref w) => parse_weekday(w)
+            .map(|day| (DateSpec::Weekday { day, next: false }, 1))
+            .or_else(|| {
+                NaiveDate::parse_from_str(w, "%Y-%m-%d")
+                    .ok()
+                    .map(|d| (DateSpec::Date(d), 1))
+            }),
+        None => None,
+    };
+    let date = date.map(|(spec, consumed)| {
+        i += consumed;
+        spec
+    });
+
+    // The time may be preceded by `at`, but only when there's a time to parse.
+    let mut time_start = i;
+    if word(tokens, time_start).map_or(false, |w| w == "at") {
+        time_start += 1;
+    }
+    let time = parse_time(tokens, time_start).map(|(time, end)| {
+        i = end;
+        time
+    });
+
+    let result = match (date, time) {
+        (None, None) => return None,
+        (Some(DateSpec::Date(date)), time) => date.and_time(time.unwrap_or_else(default_time)),
+        (None, Some(time)) => {
+            let candidate = today.and_time(time);
+            if candidate > now {
+                candidate
+            } else {
+                today.succ().and_time(time)
+            }
+        }
+        (Some(DateSpec::Weekday { day, next }), time) => {
+            let time = time.unwrap_or_else(default_time);
+            let mut date = today;
+            if next {
+                date = date.succ();
+            }
+            while date.weekday() != day || date.and_time(time) <= now {
+                date = date.succ();
+            }
+            date.and_time(time)
+        }
+    };
+
+    Some((result, i))
+}
+
+/// Parses a five-field cron expression. The minute and hour fields must be
+/// numeric so that ordinary words are never mistaken for one.
+fn parse_cron(tokens: &[(usize, &str)], start: usize) -> Option<(Schedule, usize)> {
+    if tokens.len() < start + 5 {
+        return None;
+    }
+    let numeric = |w: &str| w.chars().all(|c| c.is_ascii_digit() || "*,-/".contains(c));
+    if !numeric(tokens[start].1) || !numeric(tokens[start + 1].1) {
+        return None;
+    }
+
+    let fields: Vec<&str> = tokens[start..start + 5].iter().map(|&(_, w)| w).collect();
+    Schedule::from_str(&fields.join(" "))
+        .ok()
+        .map(|schedule| (schedule, start + 5))
+}
+
+/// Parses `day at 9am`, `weekday 8:00` or `monday and thursday 18:00` into a
+/// cron schedule. A plain `day` without a time is left for the interval parser.
+fn parse_weekly(tokens: &[(usize, &str)], start: usize) -> Option<(Schedule, usize)> {
+    let mut i = start;
+    let mut days = vec![];
+    let mut daily = false;
+    loop {
+        match word(tokens, i) {
+            Some(ref w) if w == "day" && days.is_empty() && !daily => daily = true,
+            Some(ref w) if (w == "weekday" || w == "weekdays") && !daily => {
+                days.extend(&[1, 2, 3, 4, 5]);
+            }
+            Some(ref w) if w == "and" && !days.is_empty() => {}
+            Some(ref w) if !daily => match parse_weekday(w) {
+                Some(day) => days.push(day.num_days_from_sunday()),
+                None => break,
+            },
+            _ => break,
+        }
+        i += 1;
+    }
+    if days.is_empty() && !daily {
+        return None;
+    }
+    // Don't swallow a trailing `and` that belongs to the message.
+    if word(tokens, i - 1).map_or(false, |w| w == "and") {
+        i -= 1;
+    }
+
+    let mut time_start = i;
+    if word(tokens, time_start).map_or(false, |w| w == "at") {
+        time_start += 1;
+    }
+    let time = match parse_time(tokens, time_start) {
+        Some((time, end)) => {
+            i = end;
+            time
+        }
+        None if daily => return None,
+        None => default_time(),
+    };
+
+    let days = if daily {
+        "*".to_owned()
+    } else {
+        days.sort();
+        days.dedup();
+        days.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",")
+    };
+    let expression = format!("{} {} * * {}", time.minute(), time.hour(), days);
+
+    Schedule::from_str(&expression).ok().map(|schedule| (schedule, i))
+}
+
+/// Time of day used when only a date is given.
+fn default_time() -> NaiveTime {
+    NaiveTime::from_hms(9, 0, 0)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Parses times such as `18:00`, `9am`, `9:30pm`, `9 pm`, `noon` and `midnight`.
+fn parse_time(tokens: &[(usize, &str)], start: usize) -> Option<(NaiveTime, usize)> {
+    let current = word(tokens, start)?;
+    match &*current {
+        "noon" => return Some((NaiveTime::from_hms(12, 0, 0), start + 1)),
+        "midnight" => return Some((NaiveTime::from_hms(0, 0, 0), start + 1)),
+        _ => {}
+    }
+
+    // `9am` and `9:30pm`, or `9 am` split over two words.
+    let (clock, meridiem, consumed) = if current.ends_with("am") || current.ends_with("pm") {
+        let (clock, meridiem) = current.split_at(current.len() - 2);
+        (clock.to_owned(), Some(meridiem.to_owned()), 1)
+    } else {
+        match word(tokens, start + 1) {
+            Some(ref w) if w == "am" || w == "pm" => (current.clone(), Some(w.clone()), 2),
+            _ => (current.clone(), None, 1),
+        }
+    };
+
+    let (hour, minute) = match clock.find(':') {
+        Some(colon) => {
+            let minutes = &clock[colon + 1..];
+            if minutes.len() != 2 {
+                return None;
+            }
+            (
+                clock[..colon].parse::<u32>().ok()?,
+                minutes.parse::<u32>().ok()?,
+            )
+        }
+        // A bare number is only a time with `am` or `pm` after it.
+        None if meridiem.is_some() => (clock.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+
+    let hour = match meridiem.as_ref().map(|m| &**m) {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some("am") => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0).map(|time| (time, start + consumed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    /// Wednesday 2026-10-14, 12:00.
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd(2026, 10, 14).and_hms(12, 0, 0)
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> TimeExpr {
+        TimeExpr::Absolute(NaiveDate::from_ymd(y, m, d).and_hms(h, min, 0))
+    }
+
+    #[test]
+    fn compound_durations() {
+        let expected = TimeExpr::Relative(Duration::minutes(150));
+        assert_eq!(parse("2h30m", now()), Ok((expected, "")));
+        assert_eq!(parse("2 hours 30 minutes", now()), Ok((expected, "")));
+        assert_eq!(parse("in 2 hours and 30 mins", now()), Ok((expected, "")));
+        assert_eq!(
+            parse("1 day 4 hours", now()),
+            Ok((TimeExpr::Relative(Duration::hours(28)), ""))
+        );
+        assert_eq!(
+            parse("an hour", now()),
+            Ok((TimeExpr::Relative(Duration::hours(1)), ""))
+        );
+    }
+
+    #[test]
+    fn remainder_is_the_message() {
+        assert_eq!(
+            parse("5 hours take out  the bins", now()),
+            Ok((TimeExpr::Relative(Duration::hours(5)), "take out  the bins"))
+        );
+        assert_eq!(
+            parse("1w 3 apples", now()),
+            Ok((TimeExpr::Relative(Duration::weeks(1)), "3 apples"))
+        );
+        assert_eq!(
+            parse("tomorrow at noon and then some", now()),
+            Ok((at(2026, 10, 15, 12, 0), "and then some"))
+        );
+    }
+
+    #[test]
+    fn absolute_dates() {
+        assert_eq!(parse("2026-11-02 18:00", now()), Ok((at(2026, 11, 2, 18, 0), "")));
+        assert_eq!(parse("2026-11-02", now()), Ok((at(2026, 11, 2, 9, 0), "")));
+        assert_eq!(parse("tomorrow 9am", now()), Ok((at(2026, 10, 15, 9, 0), "")));
+        assert_eq!(parse("today 9:30pm", now()), Ok((at(2026, 10, 14, 21, 30), "")));
+        assert_eq!(parse("tomorrow 9 pm", now()), Ok((at(2026, 10, 15, 21, 0), "")));
+    }
+
+    #[test]
+    fn bare_times_roll_over_to_tomorrow() {
+        assert_eq!(parse("18:00", now()), Ok((at(2026, 10, 14, 18, 0), "")));
+        assert_eq!(parse("at 11am", now()), Ok((at(2026, 10, 15, 11, 0), "")));
+        assert_eq!(parse("midnight", now()), Ok((at(2026, 10, 15, 0, 0), "")));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(parse("friday", now()), Ok((at(2026, 10, 16, 9, 0), "")));
+        assert_eq!(parse("next fri 18:30", now()), Ok((at(2026, 10, 16, 18, 30), "")));
+        assert_eq!(parse("on monday", now()), Ok((at(2026, 10, 19, 9, 0), "")));
+        // Today, but still ahead of us.
+        assert_eq!(parse("wednesday 15:00", now()), Ok((at(2026, 10, 14, 15, 0), "")));
+        // Today, but already passed.
+        assert_eq!(parse("wednesday", now()), Ok((at(2026, 10, 21, 9, 0), "")));
+        assert_eq!(parse("next wednesday 15:00", now()), Ok((at(2026, 10, 21, 15, 0), "")));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("", now()), Err(ParseError::Empty));
+        assert_eq!(parse("   ", now()), Err(ParseError::Empty));
+        assert_eq!(
+            parse("soon please", now()),
+            Err(ParseError::Unrecognised("soon".to_owned()))
+        );
+        assert_eq!(
+            parse("5 lightyears", now()),
+            Err(ParseError::Unrecognised("5".to_owned()))
+        );
+        assert_eq!(parse("25:00", now()), Err(ParseError::Unrecognised("25:00".to_owned())));
+        assert_eq!(parse("13pm", now()), Err(ParseError::Unrecognised("13pm".to_owned())));
+        assert_eq!(parse("99999999999999 weeks", now()), Err(ParseError::OutOfRange));
+    }
+
+    #[test]
+    fn resolve_in_timezone() {
+        let now = FixedOffset::east(2 * 60 * 60)
+            .from_local_datetime(&now())
+            .unwrap();
+
+        let absolute = at(2026, 10, 15, 9, 0).resolve(&now).unwrap();
+        assert_eq!(absolute.naive_utc(), NaiveDate::from_ymd(2026, 10, 15).and_hms(7, 0, 0));
+
+        let relative = TimeExpr::Relative(Duration::hours(1)).resolve(&now).unwrap();
+        assert_eq!(relative.naive_utc(), NaiveDate::from_ymd(2026, 10, 14).and_hms(11, 0, 0));
+    }
+
+    #[test]
+    fn recurrences() {
+        let cron = |expr: &str| Recurrence::Cron(expr.parse().unwrap());
+
+        assert_eq!(parse_recurrence("in 2 weeks"), Ok(None));
+        assert_eq!(
+            parse_recurrence("every monday 18:00 raid night"),
+            Ok(Some((cron("0 18 * * 1"), "raid night")))
+        );
+        assert_eq!(
+            parse_recurrence("every mon and thu at 9:30pm"),
+            Ok(Some((cron("30 21 * * 1,4"), "")))
+        );
+        assert_eq!(
+            parse_recurrence("every friday and then some"),
+            Ok(Some((cron("0 9 * * 5"), "and then some")))
+        );
+        assert_eq!(
+            parse_recurrence("every weekday 8am stand-up"),
+            Ok(Some((cron("0 8 * * 1,2,3,4,5"), "stand-up")))
+        );
+        assert_eq!(
+            parse_recurrence("every day at noon lunch"),
+            Ok(Some((cron("0 12 * * *"), "lunch")))
+        );
+        assert_eq!(
+            parse_recurrence("every day water plants"),
+            Ok(Some((Recurrence::Interval(Duration::days(1)), "water plants")))
+        );
+        assert_eq!(
+            parse_recurrence("every 2 weeks"),
+            Ok(Some((Recurrence::Interval(Duration::weeks(2)), "")))
+        );
+        assert_eq!(
+            parse_recurrence("every 1h30m stretch"),
+            Ok(Some((Recurrence::Interval(Duration::minutes(90)), "stretch")))
+        );
+        assert_eq!(
+            parse_recurrence("every 0 18 * * 1 raid night"),
+            Ok(Some((cron("0 18 * * 1"), "raid night")))
+        );
+        assert_eq!(
+            parse_recurrence("every now and then"),
+            Err(ParseError::Unrecognised("now".to_owned()))
+        );
+        assert_eq!(parse_recurrence("every 0m"), Err(ParseError::Unrecognised("0m".to_owned())));
+    }
+
+    #[test]
+    fn recurrences_round_trip() {
+        for input in &["every monday 18:00", "every 2 weeks", "every 0 */2 * * *"] {
+            let recurrence = input.parse::<Recurrence>().unwrap();
+            assert_eq!(recurrence.to_string().parse::<Recurrence>(), Ok(recurrence));
+        }
+    }
+
+    #[test]
+    fn recurrences_described_for_people() {
+        let describe = |input: &str| input.parse::<Recurrence>().unwrap().describe();
+        assert_eq!(describe("every 2 weeks"), "every 2 weeks");
+        assert_eq!(describe("every hour"), "every hour");
+        assert_eq!(describe("every 90 minutes"), "every 90 minutes");
+        assert_eq!(describe("every 1209600s"), "every 2 weeks");
+        assert_eq!(describe("every monday 18:00"), "every monday at 18:00");
+        assert_eq!(describe("every 0 */2 * * *"), "every 0 */2 * * *");
+    }
+}
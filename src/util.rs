@@ -1,12 +1,49 @@
+use chrono::{Duration, NaiveDate};
+use chrono::offset::Utc;
+use command_error::CommandError;
 use connectionpool::ConnectionPool;
+use levels::LevelConfig;
+use reminderqueue::ReminderQueue;
+use statsaggregator::StatsAggregator;
 use serenity::client::Context;
-use serenity::model::id::ChannelId;
+use serenity::model::channel::Channel;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+
+/// The longest message Discord accepts.
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// How many days back the statistics commands look when not told otherwise.
+pub const DEFAULT_DAYS: u32 = 7;
 
 pub fn get_pool(ctx: &Context) -> ConnectionPool {
     let mut data = ctx.data.lock();
     data.get_mut::<ConnectionPool>().unwrap().clone()
 }
 
+pub fn get_reminder_queue(ctx: &Context) -> ReminderQueue {
+    let data = ctx.data.lock();
+    data.get::<ReminderQueue>().unwrap().clone()
+}
+
+pub fn get_stats_aggregator(ctx: &Context) -> StatsAggregator {
+    let data = ctx.data.lock();
+    data.get::<StatsAggregator>().unwrap().clone()
+}
+
+pub fn get_level_config(ctx: &Context) -> LevelConfig {
+    let data = ctx.data.lock();
+    *data.get::<LevelConfig>().unwrap()
+}
+
+/// The first day of the last `days` days, counted in the timezone of `user_id`.
+pub fn first_day(pool: &mut ConnectionPool, user_id: UserId, days: u32) -> Result<NaiveDate, CommandError> {
+    let timezone = pool.get_timezone(user_id)?;
+    let today = Utc::now().with_timezone(&timezone).date().naive_local();
+    today
+        .checked_sub_signed(Duration::days(i64::from(days) - 1))
+        .ok_or_else(|| CommandError::Generic(format!("Can't look {} days back.", days)))
+}
+
 pub fn digits(mut number: i64) -> usize {
     let mut digits = 0;
     while number != 0 {
@@ -21,3 +58,86 @@ pub fn print_or_log_error(message: &str, channel_id: &ChannelId) {
         error!("Failed to send message: {}", e);
     }
 }
+
+/// Sends lines in as few messages as fit within Discord's length limit.
+pub fn say_in_parts(channel_id: &ChannelId, lines: &[String]) {
+    for message in join_lines(lines, MAX_MESSAGE_LENGTH) {
+        print_or_log_error(&message, channel_id);
+    }
+}
+
+/// Joins lines into messages of at most `limit` bytes, splitting long lines
+/// at spaces.
+pub fn join_lines(lines: &[String], limit: usize) -> Vec<String> {
+    let mut messages = vec![];
+    let mut message = String::new();
+    for line in lines {
+        for (i, word) in line.split(' ').enumerate() {
+            let separator = if message.is_empty() {
+                ""
+            } else if i == 0 {
+                "\n"
+            } else {
+                " "
+            };
+            if !message.is_empty() && message.len() + separator.len() + word.len() > limit {
+                messages.push(message);
+                message = String::new();
+            } else {
+                message.push_str(separator);
+            }
+            message.push_str(word);
+        }
+    }
+    if !message.is_empty() {
+        messages.push(message);
+    }
+    messages
+}
+
+/// Gets the name of a guild from the cache, falling back to its id.
+pub fn guild_name(guild_id: GuildId) -> String {
+    match guild_id.find() {
+        Some(guild) => guild.read().name.clone(),
+        None => format!("{}", guild_id),
+    }
+}
+
+/// Gets the name of a guild channel from the cache, falling back to its id.
+pub fn channel_name(channel_id: ChannelId) -> String {
+    match channel_id.find() {
+        Some(Channel::Guild(channel)) => channel.read().name.clone(),
+        _ => format!("{}", channel_id),
+    }
+}
+
+/// Gets the name of a guild member from the cache, falling back to their last
+/// known username, or their id if that isn't known either. Members that
+/// aren't in the guild anymore are marked as having left.
+pub fn member_name(guild_id: GuildId, user_id: UserId, username: Option<&String>) -> String {
+    let guild = match guild_id.find() {
+        Some(guild) => guild,
+        None => return username.cloned().unwrap_or_else(|| format!("{}", user_id)),
+    };
+    if let Some(member) = guild.read().members.get(&user_id) {
+        return member.display_name().to_string();
+    }
+
+    match username {
+        Some(name) => format!("{} (left)", name),
+        None => format!("{} (left)", user_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_messages_are_split() {
+        let lines = vec!["one two".to_owned(), "three".to_owned()];
+        assert_eq!(join_lines(&lines, 100), vec!["one two\nthree"]);
+        assert_eq!(join_lines(&lines, 8), vec!["one two", "three"]);
+        assert_eq!(join_lines(&lines, 4), vec!["one", "two", "three"]);
+    }
+}